@@ -220,6 +220,10 @@ impl<T, IndexMarker> ArenaAllocator<T, IndexMarker> {
         assert!(self.data[uuid].is_none());
         self.data[uuid] = Some(v);
     }
+    /// Returns None if the object was freed
+    pub fn get(&self, UUID(uuid, _): UUID<IndexMarker>) -> Option<&T> {
+        self.data.get(uuid).and_then(|v| v.as_ref())
+    }
     pub fn free(&mut self, UUID(uuid, _): UUID<IndexMarker>) -> T {
         self.free_slots.push(uuid);
         std::mem::replace(&mut self.data[uuid], None).unwrap()
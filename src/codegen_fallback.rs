@@ -1,22 +1,24 @@
 use std::borrow::Cow;
-use std::ops::Deref;
+use std::fmt::Write;
+use std::ops::{Deref, Range};
 
+use crate::config::{ConfigStruct, ResetConfig};
 use crate::linker::IsExtern;
 use crate::prelude::*;
 
-use crate::flattening::{DeclarationPortInfo, Instruction, Module, Port};
+use crate::flattening::{DeclarationPortInfo, Instruction, Module, Port, UnaryOperator};
 use crate::instantiation::{
-    InstantiatedModule, RealWire, RealWireDataSource, RealWirePathElem, CALCULATE_LATENCY_LATER,
+    get_reset_for, InstantiatedModule, RealWire, RealWireDataSource, RealWirePathElem, CALCULATE_LATENCY_LATER,
 };
 use crate::{linker::get_builtin_type, typing::concrete_type::ConcreteType, value::Value};
 
-fn get_type_name_size(id: TypeUUID) -> u64 {
+fn get_type_name_size(config: &ConfigStruct, id: TypeUUID) -> u64 {
     if id == get_builtin_type("int") {
         32 // TODO concrete int sizes
     } else if id == get_builtin_type("bool") {
         1
     } else {
-        println!("TODO Named Structs Size");
+        log!(config, Warn, Codegen, "TODO Named Structs Size");
         1 // todo!() // Named structs are not implemented yet
     }
 }
@@ -35,18 +37,17 @@ pub fn mangle(str: &str) -> String {
 /// Creates the Verilog variable declaration for tbis variable.
 ///
 /// IE for `int[15] myVar` it creates `[31:0] myVar[14:0]`
-fn typ_to_declaration(mut typ: &ConcreteType, var_name: &str) -> String {
+fn typ_to_declaration(config: &ConfigStruct, mut typ: &ConcreteType, var_name: &str) -> String {
     let mut array_string = String::new();
     while let ConcreteType::Array(arr) = typ {
         let (content_typ, size) = arr.deref();
         let sz = size.unwrap_value().unwrap_integer();
-        use std::fmt::Write;
         write!(array_string, "[{}:0]", sz - 1).unwrap();
         typ = content_typ;
     }
     match typ {
         ConcreteType::Named(id) => {
-            let sz = get_type_name_size(*id);
+            let sz = get_type_name_size(config, *id);
             if sz == 1 {
                 format!("{array_string} {var_name}")
             } else {
@@ -58,6 +59,20 @@ fn typ_to_declaration(mut typ: &ConcreteType, var_name: &str) -> String {
     }
 }
 
+/// SystemVerilog reduction operators only work on packed vectors. For unpacked arrays we build a balanced tree of binary operators instead
+fn reduction_tree(op_text: &str, arr_name: &str, from: usize, to: usize) -> String {
+    if to - from == 1 {
+        format!("{arr_name}[{from}]")
+    } else {
+        let mid = from + (to - from) / 2;
+        format!(
+            "({} {op_text} {})",
+            reduction_tree(op_text, arr_name, from, mid),
+            reduction_tree(op_text, arr_name, mid, to)
+        )
+    }
+}
+
 fn wire_name_with_latency(wire: &RealWire, absolute_latency: i64, use_latency: bool) -> Cow<str> {
     assert!(wire.absolute_latency <= absolute_latency);
 
@@ -76,17 +91,54 @@ fn wire_name_self_latency(wire: &RealWire, use_latency: bool) -> Cow<str> {
     wire_name_with_latency(wire, wire.absolute_latency, use_latency)
 }
 
-struct CodeGenerationContext<'g, 'out, Stream: std::fmt::Write> {
+/// `always_ff`, with the reset in its sensitivity list if it is asynchronous
+fn always_ff_header(reset: Option<&ResetConfig>) -> String {
+    match reset {
+        Some(reset) if reset.is_async => {
+            let edge = if reset.active_low { "negedge" } else { "posedge" };
+            format!("always_ff @(posedge clk or {edge} {}) begin", reset.port_name())
+        }
+        _ => "always_ff @(posedge clk) begin".to_owned(),
+    }
+}
+
+/// Delays `from` by one cycle into `to`. It is reset to zero, unless latency registers are excluded from the reset
+fn latency_register(typ: &ConcreteType, from: &str, to: &str, reset: Option<&ResetConfig>) -> String {
+    match reset {
+        Some(reset) if reset.reset_latency_registers => {
+            let zero = if let ConcreteType::Array(_) = typ { "'{default: '0}" } else { "'0" };
+            let header = always_ff_header(Some(reset));
+            format!("{header} if({}) {to} <= {zero}; else {to} <= {from}; end", reset.active_condition())
+        }
+        _ => format!("{} {to} <= {from}; end", always_ff_header(None)),
+    }
+}
+
+struct CodeGenerationContext<'g, 'out> {
+    linker: &'g Linker,
     md: &'g Module,
     instance: &'g InstantiatedModule,
-    program_text: &'out mut Stream,
+    program_text: &'out mut String,
+    source_map: Vec<(Range<usize>, Span)>,
 
     use_latency: bool,
+    /// Whether this module's own state is reset. See [get_reset_for]
+    reset: Option<&'g ResetConfig>,
+    /// See [InstantiatedModule::has_reset_port]
+    reset_port: Option<&'g ResetConfig>,
 
     needed_untils : FlatAlloc<i64, WireIDMarker>
 }
 
-impl<'g, 'out, Stream: std::fmt::Write> CodeGenerationContext<'g, 'out, Stream> {
+impl<'g, 'out> CodeGenerationContext<'g, 'out> {
+    /// Records that everything written since `start` was generated from the given instruction
+    fn map_to_source(&mut self, start: usize, original_instruction: FlatID) {
+        let end = self.program_text.len();
+        if end > start {
+            self.source_map.push((start..end, self.md.get_instruction_span(original_instruction)));
+        }
+    }
+
     /// This is for making the resulting Verilog a little nicer to read
     fn can_inline(&self, wire: &RealWire) -> bool {
         match &wire.source {
@@ -128,6 +180,11 @@ impl<'g, 'out, Stream: std::fmt::Write> CodeGenerationContext<'g, 'out, Stream>
         result
     }
 
+    /// The reset of the state and latency registers in this domain, if this module's state is reset at all
+    fn reset_for_domain(&self, domain: DomainID) -> Option<&'g ResetConfig> {
+        self.reset.filter(|reset| reset.applies_to_domain(&self.md.domains[domain].name))
+    }
+
     fn add_latency_registers(&mut self, wire_id: WireID, w: &RealWire) -> Result<(), std::fmt::Error> {
         if self.use_latency {
             // Can do 0 iterations, when w.needed_until == w.absolute_latency. Meaning it's only needed this cycle
@@ -137,12 +194,10 @@ impl<'g, 'out, Stream: std::fmt::Write> CodeGenerationContext<'g, 'out, Stream>
                 let from = wire_name_with_latency(w, i, self.use_latency);
                 let to = wire_name_with_latency(w, i + 1, self.use_latency);
 
-                let var_decl = typ_to_declaration(&w.typ, &to);
+                let var_decl = typ_to_declaration(&self.linker.config, &w.typ, &to);
 
-                writeln!(
-                    self.program_text,
-                    "/*latency*/ logic {var_decl}; always_ff @(posedge clk) begin {to} <= {from}; end"
-                ).unwrap();
+                let register = latency_register(&w.typ, &from, &to, self.reset_for_domain(w.domain));
+                writeln!(self.program_text, "/*latency*/ logic {var_decl}; {register}").unwrap();
             }
         }
         Ok(())
@@ -174,12 +229,15 @@ impl<'g, 'out, Stream: std::fmt::Write> CodeGenerationContext<'g, 'out, Stream>
         let comment_text = if commented_out { "// " } else { "" };
         // First output the interface of the module
         write!(self.program_text, "{comment_text}module {}(\n{comment_text}\tinput clk", mangle(&self.instance.name)).unwrap();
+        if let Some(reset) = self.reset_port {
+            write!(self.program_text, ",\n{comment_text}\tinput {}", reset.port_name()).unwrap();
+        }
         for (_id, port) in self.instance.interface_ports.iter_valids() {
             let port_wire = &self.instance.wires[port.wire];
             let input_or_output = if port.is_input { "input" } else { "output" };
             let wire_doc = port_wire.source.wire_or_reg();
             let wire_name = wire_name_self_latency(port_wire, self.use_latency);
-            let wire_decl = typ_to_declaration(&port_wire.typ, &wire_name);
+            let wire_decl = typ_to_declaration(&self.linker.config, &port_wire.typ, &wire_name);
             write!(
                 self.program_text,
                 ",\n{comment_text}\t{input_or_output} {wire_doc} {wire_decl}"
@@ -196,20 +254,11 @@ impl<'g, 'out, Stream: std::fmt::Write> CodeGenerationContext<'g, 'out, Stream>
     }
 
     /// Pass a `to` parameter to say to what the constant should be assigned.  
-    fn write_constant(&mut self, to : &str, value : &Value) {
-        match value {
-            Value::Bool(_) | Value::Integer(_) | Value::Unset => {
-                let v_str = value.inline_constant_to_string();
-                write!(self.program_text, "{to} = {v_str};\n").unwrap();
-            }
-            Value::Array(arr) => {
-                for (idx, v) in arr.iter().enumerate() {
-                    let new_to = format!("{to}[{idx}]");
-                    self.write_constant(&new_to, v);
-                }
-            }
-            Value::Error => unreachable!("Error values should never have reached codegen!"),
-        }
+    /// 
+    /// `arrow_str` is the assignment operator, `=` or `<=`
+    fn write_constant(&mut self, to : &str, arrow_str : &str, value : &Value) {
+        let v_str = value.constant_to_string();
+        write!(self.program_text, "{to} {arrow_str} {v_str};\n").unwrap();
     }
 
     fn write_wire_declarations(&mut self) {
@@ -228,9 +277,10 @@ impl<'g, 'out, Stream: std::fmt::Write> CodeGenerationContext<'g, 'out, Stream>
                 }
             }
             let wire_or_reg = w.source.wire_or_reg();
+            let start = self.program_text.len();
         
             let wire_name = wire_name_self_latency(w, self.use_latency);
-            let wire_decl = typ_to_declaration(&w.typ, &wire_name);
+            let wire_decl = typ_to_declaration(&self.linker.config, &w.typ, &wire_name);
             write!(self.program_text, "{wire_or_reg} {wire_decl}").unwrap();
         
             match &w.source {
@@ -240,12 +290,25 @@ impl<'g, 'out, Stream: std::fmt::Write> CodeGenerationContext<'g, 'out, Stream>
                     writeln!(self.program_text, " = {wire_name}{path};").unwrap();
                 }
                 RealWireDataSource::UnaryOp { op, right } => {
-                    writeln!(
-                        self.program_text,
-                        " = {}{};",
-                        op.op_text(),
-                        self.wire_name(*right, w.absolute_latency)
-                    ).unwrap();
+                    let right_name = self.wire_name(*right, w.absolute_latency);
+                    let identity = match op {
+                        UnaryOperator::Not | UnaryOperator::Negate => None,
+                        UnaryOperator::And => Some("1'b1"),
+                        UnaryOperator::Or | UnaryOperator::Xor => Some("1'b0"),
+                        UnaryOperator::Sum => Some("0"),
+                        UnaryOperator::Product => Some("1"),
+                    };
+                    if let Some(identity) = identity {
+                        let arr_size = self.instance.wires[*right].typ.unwrap_array_size();
+                        let expr = if arr_size == 0 {
+                            identity.to_owned()
+                        } else {
+                            reduction_tree(op.op_text(), &right_name, 0, arr_size)
+                        };
+                        writeln!(self.program_text, " = {expr};").unwrap();
+                    } else {
+                        writeln!(self.program_text, " = {}{right_name};", op.op_text()).unwrap();
+                    }
                 }
                 RealWireDataSource::BinaryOp { op, left, right } => {
                     writeln!(
@@ -259,8 +322,21 @@ impl<'g, 'out, Stream: std::fmt::Write> CodeGenerationContext<'g, 'out, Stream>
                 RealWireDataSource::Constant { value } => {
                     // Trivial constants (bools & ints) should have been inlined already
                     // So appearences of this are always arrays or other compound types
-                    writeln!(self.program_text, ";").unwrap();
-                    self.write_constant(&wire_name, value);
+                    writeln!(self.program_text, " = {};", value.constant_to_string()).unwrap();
+                }
+                RealWireDataSource::ArrayLiteral { elements } => {
+                    // Listed from the highest index down, see [Value::constant_to_string]
+                    let element_names: Vec<_> = elements
+                        .iter()
+                        .rev()
+                        .map(|elem| self.wire_name(*elem, w.absolute_latency))
+                        .collect();
+                    writeln!(self.program_text, " = '{{{}}};", element_names.join(", ")).unwrap();
+                }
+                RealWireDataSource::CrossDomain { from, group_leader: _ } => {
+                    // Latencies of different domains aren't comparable, so just take the crossing point as-is
+                    let from_wire = &self.instance.wires[*from];
+                    writeln!(self.program_text, " = {};", wire_name_self_latency(from_wire, self.use_latency)).unwrap();
                 }
                 RealWireDataSource::ReadOnly => {
                     writeln!(self.program_text, ";").unwrap();
@@ -270,13 +346,15 @@ impl<'g, 'out, Stream: std::fmt::Write> CodeGenerationContext<'g, 'out, Stream>
                     sources: _,
                 } => {
                     writeln!(self.program_text, ";").unwrap();
-                    if let Some(initial_value) = is_state {
+                    // With a reset, the initial value is assigned in write_multiplexers instead
+                    if let (Some(initial_value), None) = (is_state, self.reset_for_domain(w.domain)) {
                         let to = format!("initial {wire_name}");
-                        self.write_constant(&to, initial_value);
+                        self.write_constant(&to, "=", initial_value);
                     }
                 }
             }
             self.add_latency_registers(wire_id, w).unwrap();
+            self.map_to_source(start, w.original_instruction);
         }
     }
 
@@ -288,8 +366,14 @@ impl<'g, 'out, Stream: std::fmt::Write> CodeGenerationContext<'g, 'out, Stream>
                 .expect("Invalid submodules are impossible to remain by the time codegen happens");
             let sm_instance_name = mangle(&sm_inst.name);
             let sm_name = &sm.name;
+            let start = self.program_text.len();
             writeln!(self.program_text, "{sm_instance_name} {sm_name}(").unwrap();
             write!(self.program_text, "\t.clk(clk)").unwrap();
+            // If a submodule has a reset port, then so do we
+            if sm_inst.has_reset_port {
+                let sm_rst = self.reset_port.expect("The parent of a module with a reset port has one too").port_name();
+                write!(self.program_text, ",\n\t.{sm_rst}({sm_rst})").unwrap();
+            }
             for (port_id, iport) in sm_inst.interface_ports.iter_valids() {
                 let port_name =
                     wire_name_self_latency(&sm_inst.wires[iport.wire], self.use_latency);
@@ -305,6 +389,7 @@ impl<'g, 'out, Stream: std::fmt::Write> CodeGenerationContext<'g, 'out, Stream>
                 write!(self.program_text, ",\n\t.{port_name}({wire_name})").unwrap();
             }
             writeln!(self.program_text, "\n);").unwrap();
+            self.map_to_source(start, sm.original_instruction);
         }
     }
 
@@ -312,19 +397,33 @@ impl<'g, 'out, Stream: std::fmt::Write> CodeGenerationContext<'g, 'out, Stream>
         for (_id, w) in &self.instance.wires {
             match &w.source {
                 RealWireDataSource::Multiplexer { is_state, sources } => {
+                    let start = self.program_text.len();
                     let output_name = wire_name_self_latency(w, self.use_latency);
+                    // State that was never given an initial value has nothing to reset to
+                    let reset_value = match (is_state, self.reset_for_domain(w.domain)) {
+                        (Some(Value::Unset), _) | (_, None) | (None, _) => None,
+                        (Some(initial_value), Some(reset)) => Some((initial_value, reset)),
+                    };
                     let arrow_str = if is_state.is_some() {
-                        writeln!(self.program_text, "always_ff @(posedge clk) begin").unwrap();
+                        let header = always_ff_header(reset_value.map(|(_, reset)| reset));
+                        writeln!(self.program_text, "{header}").unwrap();
+                        if let Some((initial_value, reset)) = reset_value {
+                            writeln!(self.program_text, "\tif({}) begin", reset.active_condition()).unwrap();
+                            let tabbed_name = format!("\t\t{output_name}");
+                            self.write_constant(&tabbed_name, "<=", initial_value);
+                            writeln!(self.program_text, "\tend else begin").unwrap();
+                        }
                         "<="
                     } else {
                         writeln!(self.program_text, "always_comb begin\n\t// Combinatorial wires are not defined when not valid. This is just so that the synthesys tool doesn't generate latches").unwrap();
                         let invalid_val = w.typ.get_initial_val();
                         let tabbed_name = format!("\t{output_name}");
-                        self.write_constant(&tabbed_name, &invalid_val);
+                        self.write_constant(&tabbed_name, "=", &invalid_val);
                         "="
                     };
     
                     for s in sources {
+                        let source_start = self.program_text.len();
                         let path = self.wire_ref_path_to_string(&s.to_path, w.absolute_latency);
                         let from_name = self.wire_name(s.from.from, w.absolute_latency);
                         self.program_text.write_char('\t').unwrap();
@@ -334,8 +433,13 @@ impl<'g, 'out, Stream: std::fmt::Write> CodeGenerationContext<'g, 'out, Stream>
                             write!(self.program_text, "if({invert}{cond_name}) ").unwrap();
                         }
                         writeln!(self.program_text, "{output_name}{path} {arrow_str} {from_name};").unwrap();
+                        self.map_to_source(source_start, self.instance.wires[s.from.from].original_instruction);
+                    }
+                    if reset_value.is_some() {
+                        writeln!(self.program_text, "\tend").unwrap();
                     }
                     writeln!(self.program_text, "end").unwrap();
+                    self.map_to_source(start, w.original_instruction);
                 }
                 RealWireDataSource::ReadOnly => {}
                 RealWireDataSource::Select { root: _, path: _ } => {}
@@ -346,6 +450,8 @@ impl<'g, 'out, Stream: std::fmt::Write> CodeGenerationContext<'g, 'out, Stream>
                     right: _,
                 } => {}
                 RealWireDataSource::Constant { value: _ } => {}
+                RealWireDataSource::ArrayLiteral { elements: _ } => {}
+                RealWireDataSource::CrossDomain { from: _, group_leader: _ } => {}
             }
         }
     }
@@ -402,6 +508,19 @@ impl Value {
             Value::Error => unreachable!("Error values should never have reached codegen!"),
         }
     }
+
+    /// Arrays are written as SystemVerilog assignment patterns.
+    /// 
+    /// Elements are listed from the highest index down, because arrays are declared as `[N-1:0]`
+    fn constant_to_string(&self) -> Cow<str> {
+        match self {
+            Value::Array(arr) => {
+                let elements: Vec<_> = arr.iter().rev().map(|v| v.constant_to_string()).collect();
+                Cow::Owned(format!("'{{{}}}", elements.join(", ")))
+            }
+            other => other.inline_constant_to_string(),
+        }
+    }
 }
 
 impl Module {
@@ -431,17 +550,162 @@ impl RealWireDataSource {
     }
 }
 
-pub fn gen_verilog_code(md: &Module, instance: &InstantiatedModule, use_latency: bool) -> String {
+/// Generated code, together with which byte ranges of the output came from which spans in the module's source file
+pub struct GeneratedCode {
+    pub code: String,
+    pub source_map: Vec<(Range<usize>, Span)>,
+}
+
+pub fn gen_verilog_code_with_source_map(linker: &Linker, md: &Module, instance: &InstantiatedModule, use_latency: bool) -> GeneratedCode {
     let mut program_text = String::new();
 
     let mut ctx = CodeGenerationContext {
+        linker,
         md,
         instance,
         program_text: &mut program_text,
+        source_map: Vec::new(),
         use_latency,
+        reset: get_reset_for(linker, md),
+        reset_port: linker.config.reset.as_ref().filter(|_| instance.has_reset_port),
         needed_untils: instance.compute_needed_untils()
     };
     ctx.write_verilog_code();
+    let source_map = ctx.source_map;
+
+    GeneratedCode {
+        code: program_text,
+        source_map,
+    }
+}
 
-    program_text
+pub fn gen_verilog_code(linker: &Linker, md: &Module, instance: &InstantiatedModule, use_latency: bool) -> String {
+    gen_verilog_code_with_source_map(linker, md, instance, use_latency).code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler_top::test_util::verilog_of;
+
+    fn reset(is_async: bool, active_low: bool, reset_latency_registers: bool) -> ResetConfig {
+        ResetConfig {
+            is_async,
+            active_low,
+            reset_latency_registers,
+            only_modules: Vec::new(),
+            only_domains: Vec::new(),
+        }
+    }
+
+    fn int() -> ConcreteType {
+        ConcreteType::Named(get_builtin_type("int"))
+    }
+
+    #[test]
+    fn sync_reset_is_not_in_the_sensitivity_list() {
+        assert_eq!(always_ff_header(None), "always_ff @(posedge clk) begin");
+        assert_eq!(always_ff_header(Some(&reset(false, false, true))), "always_ff @(posedge clk) begin");
+        assert_eq!(always_ff_header(Some(&reset(false, true, true))), "always_ff @(posedge clk) begin");
+    }
+
+    #[test]
+    fn async_reset_is_in_the_sensitivity_list() {
+        assert_eq!(always_ff_header(Some(&reset(true, false, true))), "always_ff @(posedge clk or posedge rst) begin");
+        assert_eq!(always_ff_header(Some(&reset(true, true, true))), "always_ff @(posedge clk or negedge rst_n) begin");
+    }
+
+    #[test]
+    fn active_low_reset() {
+        let active_high = reset(false, false, true);
+        let active_low = reset(false, true, true);
+        assert_eq!((active_high.port_name(), active_high.active_condition()), ("rst", "rst"));
+        assert_eq!((active_low.port_name(), active_low.active_condition()), ("rst_n", "!rst_n"));
+        assert_eq!(
+            latency_register(&int(), "a", "_a_D1", Some(&active_low)),
+            "always_ff @(posedge clk) begin if(!rst_n) _a_D1 <= '0; else _a_D1 <= a; end"
+        );
+    }
+
+    #[test]
+    fn latency_registers_are_reset_to_zero() {
+        assert_eq!(
+            latency_register(&int(), "a", "_a_D1", Some(&reset(true, false, true))),
+            "always_ff @(posedge clk or posedge rst) begin if(rst) _a_D1 <= '0; else _a_D1 <= a; end"
+        );
+    }
+
+    #[test]
+    fn latency_registers_can_be_excluded_from_reset() {
+        let plain = "always_ff @(posedge clk) begin _a_D1 <= a; end";
+        assert_eq!(latency_register(&int(), "a", "_a_D1", None), plain);
+        assert_eq!(latency_register(&int(), "a", "_a_D1", Some(&reset(true, false, false))), plain);
+    }
+
+    #[test]
+    fn reset_can_be_limited_to_modules_and_domains() {
+        let mut limited = reset(false, false, true);
+        limited.only_modules = vec!["FIFO".to_owned()];
+        limited.only_domains = vec!["read".to_owned()];
+        assert!(limited.applies_to_module("FIFO") && !limited.applies_to_module("Counter"));
+        assert!(limited.applies_to_domain("read") && !limited.applies_to_domain("write"));
+
+        let everything = reset(false, false, true);
+        assert!(everything.applies_to_module("Counter") && everything.applies_to_domain("write"));
+    }
+
+    #[test]
+    fn array_constants_are_assignment_patterns() {
+        let row = |a, b| Value::Array(vec![Value::Bool(a), Value::Bool(b)].into_boxed_slice());
+        let table = Value::Array(vec![row(true, false), row(false, false)].into_boxed_slice());
+        assert_eq!(table.constant_to_string(), "'{'{1'b0, 1'b0}, '{1'b0, 1'b1}}");
+    }
+
+    #[test]
+    fn generative_array_literal_is_one_assignment() {
+        let code = verilog_of(
+            "module LookupTable {
+                interface LookupTable : int idx -> int value
+                gen int[4] TABLE = [3, 1, 4, 1]
+                value = TABLE[idx]
+            }",
+            "LookupTable",
+        );
+        assert!(code.contains("= '{1, 4, 1, 3};"), "{code}");
+        assert!(!code.contains("[0] ="), "{code}");
+    }
+
+    #[test]
+    fn runtime_array_literal_is_one_assignment() {
+        let code = verilog_of(
+            "module Pair {
+                interface Pair : int a, int b -> int[2] pair
+                pair = [a, b]
+            }",
+            "Pair",
+        );
+        assert!(code.contains("= '{b, a};"), "{code}");
+    }
+
+    #[test]
+    fn reduction_tree_is_balanced() {
+        assert_eq!(reduction_tree("+", "a", 0, 1), "a[0]");
+        assert_eq!(reduction_tree("+", "a", 0, 3), "(a[0] + (a[1] + a[2]))");
+        assert_eq!(reduction_tree("+", "a", 0, 4), "((a[0] + a[1]) + (a[2] + a[3]))");
+        assert_eq!(reduction_tree("^", "a", 2, 4), "(a[2] ^ a[3])");
+    }
+
+    #[test]
+    fn reductions_over_unpacked_arrays() {
+        let code = verilog_of(
+            "module Reduce {
+                interface Reduce : int[4] values, bool[0] flags -> int total, bool all_of_none
+                total = +values
+                all_of_none = &flags
+            }",
+            "Reduce",
+        );
+        assert!(code.contains("= ((values[0] + values[1]) + (values[2] + values[3]));"), "{code}");
+        assert!(code.contains("= 1'b1;"), "{code}");
+    }
 }
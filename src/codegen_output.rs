@@ -0,0 +1,197 @@
+//! Decides which instances are generated into which files, and writes them together with a filelist for synthesis tools
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::prelude::*;
+
+use crate::codegen_fallback::{gen_verilog_code, mangle};
+use crate::config::OutputLayout;
+use crate::flattening::Module;
+use crate::instantiation::InstantiatedModule;
+
+/// The instances, followed by all of their submodule instances. Dependencies come before the instances that use them
+pub fn dependency_order<'l>(linker: &'l Linker, roots: Vec<(&'l Module, Rc<InstantiatedModule>)>) -> Vec<(&'l Module, Rc<InstantiatedModule>)> {
+    fn visit<'l>(
+        linker: &'l Linker,
+        md: &'l Module,
+        inst: Rc<InstantiatedModule>,
+        result: &mut Vec<(&'l Module, Rc<InstantiatedModule>)>,
+    ) {
+        // Yeah yeah I know O(n²) but this list shouldn't grow too big. Fix if needed
+        if result.iter().any(|(_, existing)| Rc::ptr_eq(existing, &inst)) {
+            return;
+        }
+        for (_id, sm) in &inst.submodules {
+            // Submodules that failed to instantiate are reported as errors in the instance itself
+            if let Some(sm_inst) = &sm.instance {
+                visit(linker, &linker.modules[sm.module_uuid], sm_inst.clone(), result);
+            }
+        }
+        result.push((md, inst));
+    }
+
+    let mut result = Vec::new();
+    for (md, inst) in roots {
+        visit(linker, md, inst, &mut result);
+    }
+    result
+}
+
+/// Files that did not change are not written, so tools watching the output directory only see the files that did
+fn write_file(path: &Path, content: &str) -> Result<(), Box<dyn Error + Sync + Send>> {
+    if fs::read_to_string(path).is_ok_and(|existing| existing == content) {
+        return Ok(());
+    }
+    fs::write(path, content).map_err(|reason| format!("Could not write {}: {reason}", path.display()).into())
+}
+
+/// Generates the instances into the output directory according to the [OutputLayout]. `run_name` is the file name for [OutputLayout::Single].
+/// Also writes a `<run_name>.f` filelist and a `<run_name>.tcl` script reading the files, both in dependency order.
+/// Runs that share an output directory, such as `--codegen` and `--standalone`, must have different names
+///
+/// Returns the generated files
+pub fn write_output(
+    linker: &Linker,
+    instances: &[(&Module, Rc<InstantiatedModule>)],
+    layout: OutputLayout,
+    run_name: &str,
+) -> Result<Vec<PathBuf>, Box<dyn Error + Sync + Send>> {
+    let directory = linker.config.output_directory.clone().unwrap_or_else(|| PathBuf::from("verilog_output"));
+    fs::create_dir_all(&directory)
+        .map_err(|reason| format!("Could not create output directory {}: {reason}", directory.display()))?;
+
+    // In the order the files are first needed, so the filelist has the dependencies first
+    let mut files: Vec<(String, String)> = Vec::new();
+    for (md, inst) in instances {
+        let inst_name = &inst.name;
+        if inst.errors.did_error {
+            log!(linker.config, Info, Codegen, "Not generating {inst_name}, it has errors");
+            continue;
+        }
+        log!(linker.config, Debug, Codegen, "Generating {inst_name}");
+
+        let file_name = match layout {
+            OutputLayout::PerModule => md.link_info.name.clone(),
+            OutputLayout::PerInstance => mangle(inst_name),
+            OutputLayout::Single => run_name.to_owned(),
+        };
+        let code = gen_verilog_code(linker, md, inst, true);
+        let file_idx = match files.iter().position(|(name, _)| *name == file_name) {
+            Some(file_idx) => file_idx,
+            None => {
+                let header = format!("// DO NOT EDIT THIS FILE\n// This file was generated with SUS Compiler {}\n", std::env!("CARGO_PKG_VERSION"));
+                files.push((file_name, header));
+                files.len() - 1
+            }
+        };
+        files[file_idx].1.push_str(&format!("// {inst_name}\n{code}"));
+    }
+
+    let mut paths = Vec::with_capacity(files.len());
+    for (file_name, content) in files {
+        let mut path = directory.join(file_name);
+        path.set_extension("sv");
+        write_file(&path, &content)?;
+        paths.push(path);
+    }
+
+    // Synthesis tools are often run from another directory, so use absolute paths
+    let absolute_paths: Vec<PathBuf> = paths.iter().map(|path| path.canonicalize().unwrap_or_else(|_| path.clone())).collect();
+    let mut filelist = String::new();
+    let mut tcl_script = String::new();
+    for path in &absolute_paths {
+        filelist.push_str(&format!("{}\n", path.display()));
+        tcl_script.push_str(&format!("read_verilog -sv {{{}}}\n", path.display()));
+    }
+    write_file(&directory.join(format!("{run_name}.f")), &filelist)?;
+    write_file(&directory.join(format!("{run_name}.tcl")), &tcl_script)?;
+
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler_top::test_util::instantiate;
+    use std::time::SystemTime;
+
+    const SOURCE: &str = "module multiply {
+        interface multiply : int a, int b -> int r
+        r = a * b
+    }
+    module contains_submodule {
+        interface contains_submodule : int a, int b, int c -> int r
+        int tmp = multiply(a, b)
+        r = tmp + c
+    }";
+
+    fn output_directory(test_name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("sus_codegen_output_{test_name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        directory
+    }
+
+    /// Writes `contains_submodule` and its `multiply` submodule, returns the generated files and the filelist
+    fn write_with_layout(directory: &Path, layout: OutputLayout) -> (Vec<PathBuf>, String) {
+        let (mut linker, md_id, inst) = instantiate(SOURCE, "contains_submodule");
+        linker.config.output_directory = Some(directory.to_owned());
+        let instances = dependency_order(&linker, vec![(&linker.modules[md_id], inst)]);
+        let paths = write_output(&linker, &instances, layout, "run").unwrap();
+        let filelist = fs::read_to_string(directory.join("run.f")).unwrap();
+        (paths, filelist)
+    }
+
+    #[test]
+    fn per_module_layout_lists_dependencies_first() {
+        let directory = output_directory("per_module");
+        let (paths, filelist) = write_with_layout(&directory, OutputLayout::PerModule);
+        let tcl_script = fs::read_to_string(directory.join("run.tcl")).unwrap();
+        let canonical: Vec<PathBuf> = paths.iter().map(|path| path.canonicalize().unwrap()).collect();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(paths, vec![directory.join("multiply.sv"), directory.join("contains_submodule.sv")]);
+        assert_eq!(filelist, format!("{}\n{}\n", canonical[0].display(), canonical[1].display()));
+        assert_eq!(
+            tcl_script,
+            format!("read_verilog -sv {{{}}}\nread_verilog -sv {{{}}}\n", canonical[0].display(), canonical[1].display())
+        );
+    }
+
+    #[test]
+    fn single_layout_is_one_file_named_after_the_run() {
+        let directory = output_directory("single");
+        let (paths, filelist) = write_with_layout(&directory, OutputLayout::Single);
+        let content = fs::read_to_string(directory.join("run.sv")).unwrap();
+        let canonical = paths[0].canonicalize().unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(paths, vec![directory.join("run.sv")]);
+        assert_eq!(filelist, format!("{}\n", canonical.display()));
+        assert_eq!(content.matches("// DO NOT EDIT THIS FILE").count(), 1);
+        assert_eq!(content.matches("input clk").count(), 2);
+    }
+
+    #[test]
+    fn unchanged_files_are_not_written() {
+        let directory = output_directory("unchanged");
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("file.sv");
+        let modified = || fs::metadata(&path).unwrap().modified().unwrap();
+
+        write_file(&path, "module A;").unwrap();
+        fs::File::options().write(true).open(&path).unwrap().set_modified(SystemTime::UNIX_EPOCH).unwrap();
+        write_file(&path, "module A;").unwrap();
+        let after_same_content = modified();
+        write_file(&path, "module B;").unwrap();
+        let after_new_content = modified();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(after_same_content, SystemTime::UNIX_EPOCH);
+        assert_ne!(after_new_content, SystemTime::UNIX_EPOCH);
+        assert_eq!(content, "module B;");
+    }
+}
@@ -1,14 +1,30 @@
+use std::collections::{BTreeMap, HashSet};
 use std::ffi::OsStr;
-use std::path::PathBuf;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::str::FromStr;
 
+use num::BigInt;
+
 use crate::prelude::*;
 
-use tree_sitter::Parser;
+use tree_sitter::{InputEdit, Parser};
 
 use crate::{
-    config::config, debug::SpanDebugger, errors::ErrorStore, file_position::FileText,
-    linker::FileData,
+    config::ConfigStruct,
+    debug::SpanDebugger,
+    errors::{ErrorStore, SuggestedEdit},
+    file_position::{FileText, LineCol},
+    instantiation::InstantiatedModule,
+    linker::{FileData, NameElem},
+    logging::{log_enabled, LogLevel, LogSubsystem},
+    project::{TopModule, TopTemplateArg},
+    typing::{
+        concrete_type::{ConcreteType, BOOL_CONCRETE_TYPE, INT_CONCRETE_TYPE},
+        template::{ConcreteTemplateArg, TemplateInputKind},
+    },
+    value::{TypedValue, Value},
 };
 
 use crate::flattening::{
@@ -29,24 +45,108 @@ pub trait LinkerExtraFileInfoManager {
 
 impl LinkerExtraFileInfoManager for () {}
 
+/// `--stdlib` or `SUS_STDLIB` if given. Otherwise `share/sus_compiler/std` next to the executable, or in its install prefix,
+/// so installs can be moved. The directory the build installed it in is the last resort
+pub fn standard_library_directory(config: &ConfigStruct) -> PathBuf {
+    if let Some(stdlib) = &config.stdlib_directory {
+        return stdlib.clone();
+    }
+    if let Some(exe_dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)) {
+        for prefix in [exe_dir.clone(), exe_dir.join("..")] {
+            let candidate = prefix.join("share").join("sus_compiler").join("std");
+            if candidate.is_dir() {
+                return candidate;
+            }
+        }
+    }
+    PathBuf::from_str(STD_LIB_PATH).expect("Standard library directory is not a valid path?")
+}
+
+/// The standard library, followed by the `-L` directories
+pub fn library_directories(config: &ConfigStruct) -> Vec<PathBuf> {
+    let mut directories = vec![standard_library_directory(config)];
+    directories.extend(config.library_directories.iter().cloned());
+    directories
+}
+
+/// All .sus files in the directory and its subdirectories, sorted so files are always added in the same order. Hidden directories are skipped.
+///
+/// Only fails if `directory` itself can't be read. Subdirectories that can't be read are reported and skipped
+pub fn sus_files_in_directory(config : &ConfigStruct, directory : &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    let mut visited_directories = HashSet::new();
+    visited_directories.insert(directory.canonicalize()?);
+    gather_sus_files(config, directory, &mut visited_directories, &mut found)?;
+    found.sort();
+    Ok(found)
+}
+
+/// Symlinked directories are followed, but every directory is only visited once, so symlink loops end
+fn gather_sus_files(config : &ConfigStruct, directory : &Path, visited_directories : &mut HashSet<PathBuf>, found : &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for file in std::fs::read_dir(directory)? {
+        let file_path = file?.path();
+        if file_path.is_dir() {
+            let is_hidden = file_path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            let is_new = file_path.canonicalize().is_ok_and(|canonical| visited_directories.insert(canonical));
+            if is_hidden || !is_new {
+                continue;
+            }
+            if let Err(reason) = gather_sus_files(config, &file_path, visited_directories, found) {
+                log!(config, Warn, Linker, "Skipping directory {}: {reason}", file_path.display());
+            }
+        } else if file_path.is_file() && file_path.extension() == Some(OsStr::new("sus")) {
+            found.push(file_path);
+        }
+    }
+    Ok(())
+}
+
 impl Linker {
-    pub fn add_standard_library<ExtraInfoManager : LinkerExtraFileInfoManager>(&mut self, info_mngr : &mut ExtraInfoManager) {
-        println!("Standard Library Directory: {STD_LIB_PATH}");
-        let stl_path = PathBuf::from_str(STD_LIB_PATH).expect("Standard library directory is not a valid path?");
-        self.add_all_files_in_directory(&stl_path, info_mngr);
+    /// Adds the standard library and the `-L` library directories
+    pub fn add_libraries<ExtraInfoManager : LinkerExtraFileInfoManager>(&mut self, info_mngr : &mut ExtraInfoManager) {
+        let directories = library_directories(&self.config);
+        log!(self.config, Debug, Linker, "Standard Library Directory: {}", directories[0].display());
+        for directory in &directories {
+            self.add_all_files_in_directory(directory, info_mngr);
+        }
     }
 
+    /// Also adds the files in subdirectories. A directory that can't be read is reported and skipped
     pub fn add_all_files_in_directory<ExtraInfoManager : LinkerExtraFileInfoManager>(&mut self, directory : &PathBuf, info_mngr : &mut ExtraInfoManager) {
-        for file in std::fs::read_dir(directory).unwrap() {
-            let file_path = file.unwrap().path().canonicalize().unwrap();
-            if file_path.is_file() && file_path.extension() == Some(OsStr::new("sus")) {
-                let file_text = std::fs::read_to_string(&file_path).unwrap();
-                let file_identifier : String = info_mngr.convert_filename(&file_path);
-                self.add_file(file_identifier, file_text, info_mngr);
+        let files = match sus_files_in_directory(&self.config, directory) {
+            Ok(files) => files,
+            Err(reason) => {
+                log!(self.config, Error, Linker, "Could not read directory {}: {reason}", directory.display());
+                return;
+            }
+        };
+        for file_path in files {
+            self.add_file_from_disk_if_new(&file_path, info_mngr);
+        }
+    }
+
+    /// The same file can be reached through multiple library directories, workspace folders or project manifests.
+    /// Files that can't be read are reported and skipped
+    pub fn add_file_from_disk_if_new<ExtraInfoManager : LinkerExtraFileInfoManager>(&mut self, file_path : &Path, info_mngr : &mut ExtraInfoManager) {
+        let already_added = file_path
+            .canonicalize()
+            .is_ok_and(|file_path| self.find_file(&info_mngr.convert_filename(&file_path)).is_some());
+        if !already_added {
+            if let Err(err) = self.add_file_from_disk(file_path, info_mngr) {
+                log!(self.config, Error, Linker, "{err}");
             }
         }
     }
 
+    /// Files are identified by their canonical path
+    pub fn add_file_from_disk<ExtraInfoManager : LinkerExtraFileInfoManager>(&mut self, file_path : &Path, info_mngr : &mut ExtraInfoManager) -> Result<FileUUID, String> {
+        let read_error = |reason : std::io::Error| format!("Could not read {}: {reason}", file_path.display());
+        let canonical_path = file_path.canonicalize().map_err(read_error)?;
+        let file_text = std::fs::read_to_string(&canonical_path).map_err(read_error)?;
+        let file_identifier : String = info_mngr.convert_filename(&canonical_path);
+        Ok(self.add_file(file_identifier, file_text, info_mngr))
+    }
+
     pub fn add_file<ExtraInfoManager : LinkerExtraFileInfoManager>(&mut self, file_identifier: String, text: String, info_mngr : &mut ExtraInfoManager) -> FileUUID {
         // File doesn't yet exist
         assert!(!self.files.iter().any(|fd| fd.1.file_identifier == file_identifier));
@@ -67,19 +167,14 @@ impl Linker {
             },
         );
     
-        self.with_file_builder(file_id, |builder| {
-            let mut span_debugger =
-                SpanDebugger::new("gather_initial_file_data in add_file", builder.file_data);
-            gather_initial_file_data(builder);
-            span_debugger.defuse();
-        });
+        self.gather_file(file_id, "gather_initial_file_data in add_file");
 
         info_mngr.on_file_added(file_id, self);
 
         file_id
     }
 
-    // When lsp is not used, this gives a warning
+    // Neither the LSP nor --watch use this, they update files incrementally
     #[allow(dead_code)]
     pub fn add_or_update_file<ExtraInfoManager : LinkerExtraFileInfoManager>(&mut self, file_identifier: &str, text: String, info_mngr : &mut ExtraInfoManager) {
         if let Some(file_id) = self.find_file(file_identifier) {
@@ -93,12 +188,7 @@ impl Linker {
             file_data.file_text = FileText::new(text);
             file_data.tree = tree;
             
-            self.with_file_builder(file_id, |builder| {
-                let mut span_debugger =
-                SpanDebugger::new("gather_initial_file_data in update_file", builder.file_data);
-                gather_initial_file_data(builder);
-                span_debugger.defuse();
-            });
+            self.gather_file(file_id, "gather_initial_file_data in update_file");
 
             info_mngr.on_file_updated(file_id, self);
         } else {
@@ -106,35 +196,279 @@ impl Linker {
         }
     }
 
+    /// Applies the edits to the file in order, and reparses it reusing the old syntax tree. An edit without a range replaces the whole file. 
+    /// Like LSP positions, the columns of the ranges count UTF-16 code units
+    /// 
+    /// Returns all globals that were removed from or added to the file, to be passed to [Self::recompile_dependents_of]
+    pub fn update_file_incremental<ExtraInfoManager : LinkerExtraFileInfoManager>(
+        &mut self,
+        file_id: FileUUID,
+        edits: impl IntoIterator<Item = (Option<Range<LineCol>>, String)>,
+        info_mngr : &mut ExtraInfoManager
+    ) -> HashSet<NameElem> {
+        let mut changed: HashSet<NameElem> = self.files[file_id].associated_values.iter().copied().collect();
+
+        let file_data = self.remove_everything_in_file(file_id);
+
+        for (range, new_text) in edits {
+            let file_text = &mut file_data.file_text;
+            let byte_range = match range {
+                Some(range) => file_text.utf16_linecol_to_byte_clamp(range.start)..file_text.utf16_linecol_to_byte_clamp(range.end),
+                None => 0..file_text.len(),
+            };
+            let start_position = file_text.byte_to_ts_point(byte_range.start);
+            let old_end_position = file_text.byte_to_ts_point(byte_range.end);
+            let new_end_byte = byte_range.start + new_text.len();
+
+            file_text.replace_range(byte_range.clone(), &new_text);
+
+            file_data.tree.edit(&InputEdit {
+                start_byte: byte_range.start,
+                old_end_byte: byte_range.end,
+                new_end_byte,
+                start_position,
+                old_end_position,
+                new_end_position: file_text.byte_to_ts_point(new_end_byte),
+            });
+        }
+
+        let mut parser = Parser::new();
+        parser.set_language(&tree_sitter_sus::language()).unwrap();
+        let tree = parser.parse(&file_data.file_text.file_text, Some(&file_data.tree)).unwrap();
+
+        file_data.parsing_errors = ErrorStore::new();
+        file_data.tree = tree;
+
+        self.gather_file(file_id, "gather_initial_file_data in update_file_incremental");
+
+        info_mngr.on_file_updated(file_id, self);
+
+        changed.extend(self.files[file_id].associated_values.iter().copied());
+        changed
+    }
+
+    fn gather_file(&mut self, file_id: FileUUID, span_debug_message: &str) {
+        self.with_file_builder(file_id, |builder| {
+            let mut span_debugger = SpanDebugger::new(span_debug_message, builder.file_data);
+            gather_initial_file_data(builder);
+            span_debugger.defuse();
+        });
+    }
+
+    /// The text of the file with the suggested edits of all its errors and warnings applied. See [apply_edits]
+    /// 
+    /// Returns None if there is nothing to fix
+    pub fn apply_suggested_edits(&self, file_id: FileUUID) -> Option<String> {
+        let mut edits: Vec<SuggestedEdit> = Vec::new();
+        self.for_all_errors_in_file(file_id, |err| edits.extend(err.suggestions.iter().cloned()));
+        if edits.is_empty() {
+            return None;
+        }
+        Some(apply_edits(&self.files[file_id].file_text.file_text, edits))
+    }
+
+    /// Parses types like `int`, `bool[4]` or `MyStruct[3][5]`
+    fn parse_concrete_type(&self, text: &str) -> Result<ConcreteType, String> {
+        let text = text.trim();
+        let base_end = text.find('[').unwrap_or(text.len());
+        let base_name = text[..base_end].trim();
+        let Some((type_id, _)) = self.types.iter().find(|(_, typ)| typ.link_info.name == base_name) else {
+            return Err(format!("Unknown type '{base_name}'"));
+        };
+        let mut result = ConcreteType::Named(type_id);
+
+        let mut rest = &text[base_end..];
+        while !rest.is_empty() {
+            let Some((size, after)) = rest.strip_prefix('[').and_then(|r| r.split_once(']')) else {
+                return Err(format!("Invalid array type '{text}'"));
+            };
+            let size: BigInt = size.trim().parse().map_err(|_| format!("Invalid array size '{size}' in '{text}'"))?;
+            result = ConcreteType::Array(Box::new((result, ConcreteType::Value(Value::Integer(size)))));
+            rest = after.trim_start();
+        }
+        Ok(result)
+    }
+
+    fn top_arg_to_value(arg: &TopTemplateArg) -> Result<TypedValue, String> {
+        Ok(match arg {
+            TopTemplateArg::Bool(b) => TypedValue {
+                value: Value::Bool(*b),
+                typ: BOOL_CONCRETE_TYPE,
+            },
+            TopTemplateArg::Integer(i) => TypedValue {
+                value: Value::Integer(BigInt::from(*i)),
+                typ: INT_CONCRETE_TYPE,
+            },
+            TopTemplateArg::Array(elements) => {
+                let elements = elements.iter().map(Self::top_arg_to_value).collect::<Result<Vec<_>, _>>()?;
+                let Some(content_typ) = elements.first().map(|e| e.typ.clone()) else {
+                    return Err("Empty arrays can't be given as template arguments, their type is unknown".to_owned());
+                };
+                if elements.iter().any(|e| e.typ != content_typ) {
+                    return Err("All elements of an array template argument must have the same type".to_owned());
+                }
+                let size = ConcreteType::Value(Value::Integer(BigInt::from(elements.len())));
+                TypedValue {
+                    typ: ConcreteType::Array(Box::new((content_typ, size))),
+                    value: Value::Array(elements.into_iter().map(|e| e.value).collect()),
+                }
+            }
+            TopTemplateArg::Type(name) => return Err(format!("Expected a value, but found the type '{name}'")),
+        })
+    }
+
+    /// Instantiates a top module from the project manifest or `--top` with the given template arguments, so its code can be generated.
+    /// Errors in the instance itself are reported in the module's errors, like for any other instance
+    pub fn instantiate_top(&self, top: &TopModule) -> Result<(&Module, Rc<InstantiatedModule>), String> {
+        let Some((_, md)) = self.modules.iter().find(|(_, md)| md.link_info.name == top.module) else {
+            return Err(format!("Unknown top module '{}'", top.module));
+        };
+        let link_info = &md.link_info;
+        if let Some(unknown) = top
+            .template_args
+            .keys()
+            .find(|name| !link_info.template_arguments.iter().any(|(_, input)| &&input.name == name))
+        {
+            return Err(format!("Module '{}' has no template argument '{unknown}'", top.module));
+        }
+
+        // Positional arguments are assigned in order, like in SUS code
+        let mut given_args: BTreeMap<&str, TopTemplateArg> =
+            top.template_args.iter().map(|(name, arg)| (name.as_str(), arg.clone())).collect();
+        let mut positional_values = top.positional_values.iter();
+        let mut positional_types = top.positional_types.iter();
+        for (_id, input) in &link_info.template_arguments {
+            let positional = match &input.kind {
+                TemplateInputKind::Type(_) => positional_types.next().map(|typ| TopTemplateArg::Type(typ.clone())),
+                TemplateInputKind::Generative(_) => positional_values.next().cloned(),
+            };
+            if let Some(positional) = positional {
+                if given_args.insert(input.name.as_str(), positional).is_some() {
+                    return Err(format!("Template argument '{}' of '{}' is given both by position and by name", input.name, top.module));
+                }
+            }
+        }
+        if positional_values.next().is_some() || positional_types.next().is_some() {
+            return Err(format!("Too many template arguments given for '{}'", top.module));
+        }
+
+        let mut template_args = FlatAlloc::new();
+        for (_id, input) in &link_info.template_arguments {
+            let Some(given) = given_args.get(input.name.as_str()) else {
+                return Err(format!("Top module '{}' is missing template argument '{}'", top.module, input.name));
+            };
+            let arg = match (&input.kind, given) {
+                (TemplateInputKind::Type(_), TopTemplateArg::Type(typ)) => ConcreteTemplateArg::Type(self.parse_concrete_type(typ)?),
+                (TemplateInputKind::Type(_), _) => {
+                    return Err(format!("Template argument '{}' of '{}' must be a type", input.name, top.module))
+                }
+                (TemplateInputKind::Generative(_), value) => ConcreteTemplateArg::Value(Self::top_arg_to_value(value)?),
+            };
+            template_args.alloc(arg);
+        }
+
+        let instance = md
+            .instantiations
+            .instantiate(md, self, template_args)
+            .ok_or_else(|| format!("Could not instantiate top module '{}'", top.module))?;
+        Ok((md, instance))
+    }
+
     pub fn find_file(&self, file_identifier: &str) -> Option<FileUUID> {
         self.files.find(|_id, f| f.file_identifier == file_identifier)
     }
 
     pub fn recompile_all(&mut self) {
-        // First reset all modules back to post-gather_initial_file_data
-        for (_, md) in &mut self.modules {
-            let Module {
-                link_info,
-                instructions,
-                instantiations,
-                ..
-            } = md;
-            link_info.reset_to(link_info.after_initial_parse_cp);
-            link_info.after_flatten_cp = None;
-            instructions.clear();
-            instantiations.clear_instances()
-        }
-
-        flatten_all_modules(self);
-        if config().debug_print_module_contents {
+        let everything: HashSet<NameElem> = self.modules.iter().map(|(id, _)| NameElem::Module(id))
+            .chain(self.types.iter().map(|(id, _)| NameElem::Type(id)))
+            .collect();
+
+        self.recompile(&everything);
+    }
+
+    /// Recompiles everything that may be affected by adding or removing the given globals. 
+    /// 
+    /// This is any global that references one of them, directly or through other globals, or shares a name with one of them. 
+    /// Globals with unresolved names are always recompiled, as the name may now exist
+    pub fn recompile_dependents_of(&mut self, changed: HashSet<NameElem>) {
+        // Freed globals can't be looked up anymore, but new ones with the same name still tell us who may now collide
+        let changed_names: HashSet<String> = changed.iter()
+            .filter_map(|global| match *global {
+                NameElem::Module(id) => self.modules.get(id).map(|md| md.link_info.name.clone()),
+                NameElem::Type(id) => self.types.get(id).map(|typ| typ.link_info.name.clone()),
+                NameElem::Constant(_) => None,
+            })
+            .collect();
+
+        let mut to_recompile = changed;
+        loop {
+            let mut found_new = false;
+            let all_globals = self.modules.iter().map(|(id, md)| (NameElem::Module(id), &md.link_info))
+                .chain(self.types.iter().map(|(id, typ)| (NameElem::Type(id), &typ.link_info)));
+            for (global, link_info) in all_globals {
+                if to_recompile.contains(&global) {
+                    continue;
+                }
+                let is_affected = !link_info.resolved_globals.all_resolved() || link_info.resolved_globals.iter().any(|referenced| {
+                    to_recompile.contains(referenced)
+                        || self.get_link_info(*referenced).map_or(false, |info| changed_names.contains(&info.name))
+                });
+                if is_affected {
+                    to_recompile.insert(global);
+                    found_new = true;
+                }
+            }
+            if !found_new {
+                break;
+            }
+        }
+
+        // Only the ones that still exist
+        to_recompile.retain(|global| match *global {
+            NameElem::Module(id) => self.modules.get(id).is_some(),
+            NameElem::Type(id) => self.types.get(id).is_some(),
+            NameElem::Constant(_) => false,
+        });
+
+        self.recompile(&to_recompile);
+    }
+
+    fn recompile(&mut self, to_recompile: &HashSet<NameElem>) {
+        // First reset these globals back to post-gather_initial_file_data
+        for global in to_recompile {
+            match *global {
+                NameElem::Module(id) => {
+                    let Module {
+                        link_info,
+                        instructions,
+                        instantiations,
+                        ..
+                    } = &mut self.modules[id];
+                    link_info.reset_to(link_info.after_initial_parse_cp);
+                    link_info.after_flatten_cp = None;
+                    instructions.clear();
+                    instantiations.clear_instances()
+                }
+                NameElem::Type(id) => {
+                    let typ = &mut self.types[id];
+                    typ.link_info.reset_to(typ.link_info.after_initial_parse_cp);
+                    typ.link_info.after_flatten_cp = None;
+                    typ.instructions.clear();
+                }
+                NameElem::Constant(_) => {}
+            }
+        }
+
+        flatten_all_modules(self, to_recompile);
+        if log_enabled(&self.config, LogLevel::Trace, LogSubsystem::Flatten) {
             for (_, md) in &self.modules {
                 md.print_flattened_module(&self.files[md.link_info.file]);
             }
         }
 
-        typecheck_all_modules(self);
+        typecheck_all_modules(self, to_recompile);
 
-        if config().debug_print_module_contents {
+        if log_enabled(&self.config, LogLevel::Trace, LogSubsystem::Flatten) {
             for (_, md) in &self.modules {
                 md.print_flattened_module(&self.files[md.link_info.file]);
             }
@@ -142,7 +476,10 @@ impl Linker {
 
         // Make an initial instantiation of all modules
         // Won't be possible once we have template modules
-        for (_id, md) in &self.modules {
+        for (id, md) in &self.modules {
+            if !to_recompile.contains(&NameElem::Module(id)) {
+                continue;
+            }
             //md.print_flattened_module();
             // Already instantiate any modules without parameters
             // Currently this is all modules
@@ -159,3 +496,143 @@ impl Linker {
         }
     }
 }
+
+/// Edits overlapping an earlier edit are skipped, they can be applied by running again. 
+/// Identical edits are only applied once, because the same error can be reported once per instance
+fn apply_edits(file_text: &str, mut edits: Vec<SuggestedEdit>) -> String {
+    edits.sort_by(|a, b| {
+        let (a_range, b_range) = (a.position.into_range(), b.position.into_range());
+        (a_range.start, a_range.end, &a.replace_with).cmp(&(b_range.start, b_range.end, &b.replace_with))
+    });
+    edits.dedup_by(|a, b| a.position.into_range() == b.position.into_range() && a.replace_with == b.replace_with);
+
+    let mut result = String::with_capacity(file_text.len());
+    let mut copied_up_to = 0;
+    for edit in edits {
+        let range = edit.position.into_range();
+        if range.start < copied_up_to {
+            continue;
+        }
+        result.push_str(&file_text[copied_up_to..range.start]);
+        result.push_str(&edit.replace_with);
+        copied_up_to = range.end;
+    }
+    result.push_str(&file_text[copied_up_to..]);
+    result
+}
+
+/// For tests that compile SUS code. The standard library is not added
+#[cfg(test)]
+pub mod test_util {
+    use super::*;
+    use crate::codegen_fallback::gen_verilog_code;
+    use crate::config::ConfigStruct;
+    use crate::errors::ErrorLevel;
+    use crate::instantiation::InstantiatedModule;
+    use std::rc::Rc;
+
+    pub fn compile(text: &str) -> (Linker, FileUUID) {
+        let mut linker = Linker::new(ConfigStruct::default());
+        let file_id = linker.add_file("test.sus".to_owned(), text.to_owned(), &mut ());
+        linker.recompile_all();
+        (linker, file_id)
+    }
+
+    /// The reasons of all errors in the file. Warnings are left out
+    pub fn error_messages(linker: &Linker, file_id: FileUUID) -> Vec<String> {
+        let mut messages = Vec::new();
+        linker.for_all_errors_in_file(file_id, |err| {
+            if err.level == ErrorLevel::Error {
+                messages.push(err.reason.clone());
+            }
+        });
+        messages
+    }
+
+    /// Instantiates `module_name` without template arguments. `text` must compile without errors
+    pub fn instantiate(text: &str, module_name: &str) -> (Linker, ModuleUUID, Rc<InstantiatedModule>) {
+        let (linker, file_id) = compile(text);
+        assert_eq!(error_messages(&linker, file_id), Vec::<String>::new());
+        let (md_id, md) = linker
+            .modules
+            .iter()
+            .find(|(_, md)| md.link_info.name == module_name)
+            .expect("No module with this name");
+        let instance = md
+            .instantiations
+            .instantiate(md, &linker, FlatAlloc::new())
+            .expect("Instantiation failed");
+        (linker, md_id, instance)
+    }
+
+    /// `text` must compile without errors
+    pub fn verilog_of(text: &str, module_name: &str) -> String {
+        let (linker, md_id, instance) = instantiate(text, module_name);
+        let md = &linker.modules[md_id];
+        gen_verilog_code(&linker, md, &instance, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler_top::test_util::compile;
+
+    fn edit(range: Range<usize>, replace_with: &str) -> SuggestedEdit {
+        SuggestedEdit {
+            position: Span::from(range),
+            replace_with: replace_with.to_owned(),
+        }
+    }
+
+    #[test]
+    fn edits_are_applied_in_order() {
+        let edits = vec![edit(7..8, "world"), edit(0..5, "Goodbye"), edit(5..5, ",")];
+        assert_eq!(apply_edits("Hello, X!", edits), "Goodbye,, world!");
+    }
+
+    #[test]
+    fn identical_edits_are_applied_once() {
+        let edits = vec![edit(4..4, "_"), edit(4..4, "_")];
+        assert_eq!(apply_edits("int a", edits), "int _a");
+    }
+
+    #[test]
+    fn overlapping_edits_are_skipped() {
+        // The second edit starts inside the first, the third contains the first
+        let edits = vec![edit(0..5, "abc"), edit(3..7, "xyz"), edit(0..9, "")];
+        assert_eq!(apply_edits("0123456789", edits), "abc56789");
+    }
+
+    #[test]
+    fn suggested_edits_of_a_file() {
+        let (linker, file_id) = compile(
+            "module M {
+                int a, int b
+            }",
+        );
+        assert_eq!(
+            linker.apply_suggested_edits(file_id).as_deref(),
+            Some(
+                "module M {
+                int a
+                int b
+            }"
+            )
+        );
+
+        let (linker, file_id) = compile("module M {}");
+        assert_eq!(linker.apply_suggested_edits(file_id), None);
+    }
+
+    #[test]
+    fn missing_files_are_skipped() {
+        let (mut linker, _file_id) = compile("module M {}");
+        let missing = std::env::temp_dir().join(format!("sus_missing_file_{}.sus", std::process::id()));
+        let err = linker.add_file_from_disk(&missing, &mut ()).unwrap_err();
+        assert!(err.starts_with(&format!("Could not read {}", missing.display())), "{err}");
+
+        linker.add_file_from_disk_if_new(&missing, &mut ());
+        assert_eq!(linker.files.iter().count(), 1);
+    }
+}
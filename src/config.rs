@@ -1,22 +1,208 @@
-use std::{cell::UnsafeCell, env, ffi::OsStr, path::PathBuf};
+use std::{env, ffi::OsStr, path::PathBuf};
 
 use clap::{Arg, Command};
+use serde::Deserialize;
 
+use crate::errors::DiagnosticCode;
+use crate::logging::{parse_log_filter, LogLevel, LogLevels};
+use crate::project::{Project, TopModule, MANIFEST_FILE_NAME};
+
+/// The options of one compilation, owned by its [Linker](crate::linker::Linker). [parse_args] makes one from the command line,
+/// library users start from [ConfigStruct::default]
+#[derive(Clone)]
 pub struct ConfigStruct {
     pub use_lsp: bool,
     pub lsp_debug_mode: bool,
     pub lsp_port: u16,
+    /// Talk LSP over stdin/stdout, instead of connecting to a socket opened by the editor extension
+    pub lsp_stdio: bool,
     pub codegen: bool,
-    pub debug_print_module_contents: bool,
-    pub debug_print_latency_graph: bool,
     pub codegen_module_and_dependencies_one_file: Option<String>,
+    pub reset: Option<ResetConfig>,
+    /// Apply the suggested fixes of errors and warnings to the given source files
+    pub apply_fixes: bool,
+    /// Set by the `fmt` subcommand. Formatting replaces compilation
+    pub format: Option<FormatMode>,
+    /// From `-o` or the project manifest. If None, code is generated in `verilog_output`
+    pub output_directory: Option<PathBuf>,
+    pub output_layout: OutputLayout,
+    /// Modules to generate together with their dependencies, from `--top` or the project manifest. If empty, all modules are generated
+    pub top_modules: Vec<TopModule>,
+    /// The default level of all warnings. Set by `--deny-warnings` or the project manifest
+    pub warnings: WarningPolicy,
+    /// From `-A`, `-W` and `-D`, in the order they were given. Later ones override earlier ones
+    pub lint_levels: Vec<(DiagnosticCode, WarningPolicy)>,
+    /// `--explain`: print the explanation of this code instead of compiling
+    pub explain: Option<DiagnosticCode>,
+    pub message_format: MessageFormat,
+    /// Keep running, and recompile whenever a source file changes
+    pub watch: bool,
+    pub source_discovery: SourceDiscovery,
+    /// From `--stdlib` or `SUS_STDLIB`. If None, see [crate::compiler_top::standard_library_directory]
+    pub stdlib_directory: Option<PathBuf>,
+    /// From `-L`. All .sus files in these directories are compiled along with the standard library
+    pub library_directories: Vec<PathBuf>,
+    /// From `-v`, `-q` and `--log`
+    pub log_levels: LogLevels,
+}
+
+/// How the source files were found. `--watch` repeats this to pick up files that were added or removed
+#[derive(Clone)]
+pub enum SourceDiscovery {
+    /// Given on the command line
+    Explicit,
+    /// From the `sus.toml` in this directory
+    Project(PathBuf),
+    /// All .sus files in the current directory
+    CurrentDirectory,
+}
+
+impl SourceDiscovery {
+    /// The source files as they are now. `initial_files` are the files found by [parse_args]
+    pub fn find_files(&self, config: &ConfigStruct, initial_files: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+        match self {
+            SourceDiscovery::Explicit => Ok(initial_files.iter().filter(|path| path.is_file()).cloned().collect()),
+            SourceDiscovery::Project(root) => match Project::find_in(root)? {
+                Some(project) => project.source_files(config),
+                None => Err(format!("{} was removed", root.join(MANIFEST_FILE_NAME).display())),
+            },
+            SourceDiscovery::CurrentDirectory => Ok(sus_files_in_current_directory()),
+        }
+    }
+}
+
+fn sus_files_in_current_directory() -> Vec<PathBuf> {
+    let mut file_paths = Vec::new();
+    for file in std::fs::read_dir(".").unwrap() {
+        let file_path = file.unwrap().path();
+        if file_path.is_file() && file_path.extension() == Some(OsStr::new("sus")) {
+            file_paths.push(file_path);
+        }
+    }
+    file_paths
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MessageFormat {
+    /// Colored reports through ariadne
+    Human,
+    /// One JSON object per diagnostic per line on stdout, see [crate::dev_aid::json_diagnostics]
+    Json,
+    /// A single SARIF 2.1.0 log on stdout, for code scanning integrations
+    Sarif,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WarningPolicy {
+    /// Don't report warnings
+    Allow,
+    #[default]
+    Warn,
+    /// Report warnings as errors, and fail the compilation if there are any
+    Deny,
+}
+
+/// How the generated instances are divided over files
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputLayout {
+    /// `<module>.sv`, containing all instances of the module
+    #[default]
+    PerModule,
+    /// One file per instance, named after the instance
+    PerInstance,
+    /// All instances in one file
+    Single,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FormatMode {
+    /// Overwrite the files with their formatted version
+    InPlace,
+    /// Only report which files aren't formatted, for CI
+    Check,
+}
+
+/// Opt-in reset signal for generated modules. When enabled, `state` initial values are assigned on reset instead of in `initial` blocks
+#[derive(Clone)]
+pub struct ResetConfig {
+    pub is_async: bool,
+    pub active_low: bool,
+    /// Latency registers don't carry meaningful values after reset, so leaving them out saves routing
+    pub reset_latency_registers: bool,
+    /// If empty, all modules get a reset
+    pub only_modules: Vec<String>,
+    /// If empty, the state in all domains is reset. Domains are matched by the name of their `domain` statement. The domain before the first one is named after the module
+    pub only_domains: Vec<String>,
 }
 
-pub fn config() -> &'static ConfigStruct {
-    unsafe { &*CONFIG.cf.get() }
+impl ResetConfig {
+    pub fn port_name(&self) -> &'static str {
+        if self.active_low { "rst_n" } else { "rst" }
+    }
+    /// The condition under which the reset is active, to be used in `if(...)`
+    pub fn active_condition(&self) -> &'static str {
+        if self.active_low { "!rst_n" } else { "rst" }
+    }
+    pub fn applies_to_module(&self, module_name: &str) -> bool {
+        self.only_modules.is_empty() || self.only_modules.iter().any(|m| m == module_name)
+    }
+    pub fn applies_to_domain(&self, domain_name: &str) -> bool {
+        self.only_domains.is_empty() || self.only_domains.iter().any(|d| d == domain_name)
+    }
 }
 
-pub fn parse_args() -> Vec<PathBuf> {
+impl Default for ConfigStruct {
+    fn default() -> Self {
+        ConfigStruct {
+            use_lsp: false,
+            lsp_port: 25000,
+            lsp_stdio: false,
+            lsp_debug_mode: false,
+            codegen: false,
+            codegen_module_and_dependencies_one_file: None,
+            reset: None,
+            apply_fixes: false,
+            format: None,
+            output_directory: None,
+            output_layout: OutputLayout::PerModule,
+            top_modules: Vec::new(),
+            warnings: WarningPolicy::Warn,
+            lint_levels: Vec::new(),
+            explain: None,
+            message_format: MessageFormat::Human,
+            watch: false,
+            source_discovery: SourceDiscovery::Explicit,
+            stdlib_directory: None,
+            library_directories: Vec::new(),
+            log_levels: LogLevels::default(),
+        }
+    }
+}
+
+fn validate_warning_code(code: &str) -> Result<(), &'static str> {
+    match DiagnosticCode::from_code_or_name(code) {
+        Some(code) if code.is_warning() => Ok(()),
+        Some(_) => Err("Only warnings can be allowed, warned or denied"),
+        None => Err("Unknown diagnostic code"),
+    }
+}
+
+fn validate_sus_file(file_path_str: &str) -> Result<(), &'static str> {
+    let file_path = PathBuf::from(file_path_str);
+    if !file_path.exists() {
+        Err("File does not exist")
+    } else if !file_path.is_file() {
+        Err("Is a directory")
+    } else if file_path.extension() != Some(OsStr::new("sus")) {
+        Err("Source files must end in .sus")
+    } else {
+        Ok(())
+    }
+}
+
+pub fn parse_args() -> (ConfigStruct, Vec<PathBuf>) {
     let matches = Command::new("SUS Compiler")
         .version(env!("CARGO_PKG_VERSION"))
         .author(env!("CARGO_PKG_AUTHORS"))
@@ -36,6 +222,10 @@ pub fn parse_args() -> Vec<PathBuf> {
         .arg(Arg::new("lsp")
             .long("lsp")
             .help("Enable LSP mode"))
+        .arg(Arg::new("stdio")
+            .long("stdio")
+            .help("Communicate with the LSP client over stdin/stdout instead of a TCP socket")
+            .requires("lsp"))
         .arg(Arg::new("lsp-debug")
             .long("lsp-debug")
             .hide(true)
@@ -44,36 +234,146 @@ pub fn parse_args() -> Vec<PathBuf> {
         .arg(Arg::new("codegen")
             .long("codegen")
             .help("Enable code generation for all modules. This creates a file named [ModuleName].sv per module."))
-        .arg(Arg::new("debug")
-            .long("debug")
-            .hide(true)
-            .help("Print debug information about the module contents"))
-        .arg(Arg::new("debug-latency")
-            .long("debug-latency")
-            .hide(true)
-            .help("Print latency graph for debugging"))
+        .arg(Arg::new("verbose")
+            .short('v')
+            .long("verbose")
+            .multiple_occurrences(true)
+            .help("Print what the compiler is doing. -vv also prints its internal data structures"))
+        .arg(Arg::new("quiet")
+            .short('q')
+            .long("quiet")
+            .conflicts_with("verbose")
+            .help("Only print errors and warnings"))
+        .arg(Arg::new("log")
+            .long("log")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .help("Set the log level of one subsystem, such as 'latency' or 'lsp=debug'. Subsystems are linker, flatten, instantiation, latency, codegen and lsp")
+            .validator(parse_log_filter))
         .arg(Arg::new("standalone")
             .long("standalone")
             .takes_value(true)
             .help("Generate standalone code with all dependencies in one file of the module specified. "))
+        .arg(Arg::new("reset")
+            .long("reset")
+            .takes_value(true)
+            .possible_values(["sync", "async"])
+            .help("Add a reset input to generated modules. State initial values are assigned on reset"))
+        .arg(Arg::new("reset-active-low")
+            .long("reset-active-low")
+            .help("Use an active-low reset (rst_n) instead of an active-high one (rst)")
+            .requires("reset"))
+        .arg(Arg::new("reset-no-latency")
+            .long("reset-no-latency")
+            .help("Do not reset latency registers")
+            .requires("reset"))
+        .arg(Arg::new("reset-module")
+            .long("reset-module")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .help("Only reset the state of the given module. Can be given multiple times. Defaults to all modules. Modules that contain it get a reset port to pass it on")
+            .requires("reset"))
+        .arg(Arg::new("reset-domain")
+            .long("reset-domain")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .help("Only reset the state and latency registers in domains with this name. Can be given multiple times. Defaults to all domains")
+            .requires("reset"))
+        .arg(Arg::new("top")
+            .long("top")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .help("Generate the given module with its dependencies, such as 'FIFO::<DEPTH = 64, READY_SLACK = 3; int>'. Can be given multiple times")
+            .validator(|top : &str| TopModule::parse(top).map(|_| ())))
+        .arg(Arg::new("message-format")
+            .long("message-format")
+            .takes_value(true)
+            .possible_values(["human", "json", "sarif"])
+            .default_value("human")
+            .help("How errors and warnings are printed. 'json' prints one JSON object per line on stdout, 'sarif' prints a SARIF 2.1.0 log on stdout"))
+        .arg(Arg::new("output-dir")
+            .short('o')
+            .long("output-dir")
+            .takes_value(true)
+            .help("Directory to generate the code in. Defaults to 'verilog_output'"))
+        .arg(Arg::new("layout")
+            .long("layout")
+            .takes_value(true)
+            .possible_values(["per-module", "per-instance", "single"])
+            .help("Generate one file per module (default), one per instance, or a single file for everything"))
+        .arg(Arg::new("allow")
+            .short('A')
+            .long("allow")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .help("Don't report this warning, by code (W0001) or name (unused_variable)")
+            .validator(validate_warning_code))
+        .arg(Arg::new("warn")
+            .short('W')
+            .long("warn")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .help("Report this warning as a warning")
+            .validator(validate_warning_code))
+        .arg(Arg::new("deny")
+            .short('D')
+            .long("deny")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .help("Report this warning as an error, and fail the compilation if it occurs")
+            .validator(validate_warning_code))
+        .arg(Arg::new("deny-warnings")
+            .long("deny-warnings")
+            .help("Report all warnings as errors, and fail the compilation if any occur"))
+        .arg(Arg::new("explain")
+            .long("explain")
+            .takes_value(true)
+            .help("Explain an error or warning code, such as E0005 or W0001")
+            .validator(|code : &str| DiagnosticCode::from_code_or_name(code).map(|_| ()).ok_or("Unknown diagnostic code")))
+        .arg(Arg::new("stdlib")
+            .long("stdlib")
+            .takes_value(true)
+            .help("Directory of the standard library. Can also be set with the SUS_STDLIB environment variable"))
+        .arg(Arg::new("library")
+            .short('L')
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .help("Also compile all .sus files in this directory and its subdirectories. Can be given multiple times"))
+        .arg(Arg::new("watch")
+            .long("watch")
+            .help("Keep running, and recompile and regenerate the code whenever a source file or the standard library changes"))
+        .arg(Arg::new("fix")
+            .long("fix")
+            .help("Apply the fixes suggested by errors and warnings to the given source files"))
         .arg(Arg::new("files")
             .multiple_values(true)
             .help(".sus Files")
-            .validator(|file_path_str : &str| {
-                let file_path = PathBuf::from(file_path_str);
-                if !file_path.exists() {
-                    Err("File does not exist")
-                } else if !file_path.is_file() {
-                    Err("Is a directory")
-                } else if file_path.extension() != Some(OsStr::new("sus")) {
-                    Err("Source files must end in .sus")
-                } else {
-                    Ok(())
-                }
-            }))
+            .validator(validate_sus_file))
+        .subcommand(Command::new("fmt")
+            .about("Format .sus files in place")
+            .arg(Arg::new("check")
+                .long("check")
+                .help("Don't write the files, but exit with an error if any of them isn't formatted"))
+            .arg(Arg::new("files")
+                .multiple_values(true)
+                .help(".sus Files")
+                .validator(validate_sus_file)))
         .get_matches();
 
-    let config = unsafe { &mut *CONFIG.cf.get() };
+    let mut config = ConfigStruct::default();
+
+    // First, so messages while loading the project are already filtered
+    let level = match matches.occurrences_of("verbose") {
+        _ if matches.is_present("quiet") => LogLevel::Warn,
+        0 => LogLevel::Info,
+        1 => LogLevel::Debug,
+        _ => LogLevel::Trace,
+    };
+    let filters = match matches.values_of("log") {
+        Some(filters) => filters.map(|filter| parse_log_filter(filter).unwrap()).collect(),
+        None => Vec::new(),
+    };
+    config.log_levels = LogLevels { level, filters };
 
     if let Some(socket) = matches.value_of("socket") {
         config.lsp_port = u16::from_str_radix(socket, 10).unwrap();
@@ -81,50 +381,122 @@ pub fn parse_args() -> Vec<PathBuf> {
 
     config.use_lsp = matches.is_present("lsp");
     config.lsp_debug_mode = matches.is_present("lsp-debug");
+    config.lsp_stdio = matches.is_present("stdio");
     config.codegen = matches.is_present("codegen");
-    config.debug_print_module_contents = matches.is_present("debug");
-    config.debug_print_latency_graph = matches.is_present("debug-latency");
+    config.apply_fixes = matches.is_present("fix");
+    config.watch = matches.is_present("watch");
+    config.stdlib_directory = match matches.value_of("stdlib") {
+        Some(stdlib) => Some(PathBuf::from(stdlib)),
+        None => env::var_os("SUS_STDLIB").filter(|stdlib| !stdlib.is_empty()).map(PathBuf::from),
+    };
+    if let Some(library_directories) = matches.values_of("library") {
+        config.library_directories = library_directories.map(PathBuf::from).collect();
+    }
+    config.message_format = match matches.value_of("message-format") {
+        Some("json") => MessageFormat::Json,
+        Some("sarif") => MessageFormat::Sarif,
+        _ => MessageFormat::Human,
+    };
 
     if let Some(standalone) = matches.value_of("standalone") {
         config.codegen_module_and_dependencies_one_file = Some(standalone.to_string());
     }
 
+    if let Some(tops) = matches.values_of("top") {
+        config.top_modules = tops.map(|top| TopModule::parse(top).unwrap()).collect();
+        config.codegen = true;
+    }
+
+    if let Some(reset_kind) = matches.value_of("reset") {
+        config.reset = Some(ResetConfig {
+            is_async: reset_kind == "async",
+            active_low: matches.is_present("reset-active-low"),
+            reset_latency_registers: !matches.is_present("reset-no-latency"),
+            only_modules: matches
+                .values_of("reset-module")
+                .map(|mds| mds.map(|m| m.to_string()).collect())
+                .unwrap_or_default(),
+            only_domains: matches
+                .values_of("reset-domain")
+                .map(|domains| domains.map(|d| d.to_string()).collect())
+                .unwrap_or_default(),
+        });
+    }
+
+    let files_matches = if let Some(fmt_matches) = matches.subcommand_matches("fmt") {
+        config.format = Some(if fmt_matches.is_present("check") {
+            FormatMode::Check
+        } else {
+            FormatMode::InPlace
+        });
+        fmt_matches
+    } else {
+        &matches
+    };
+
     let mut file_paths: Vec<PathBuf> = Vec::new();
-    if let Some(files) = matches.values_of("files") {
+    if let Some(files) = files_matches.values_of("files") {
         for file in files {
             file_paths.push(PathBuf::from(file));
         }
     }
 
-    // For debugging, if no files are provided
-    if file_paths.is_empty() {
-        for file in std::fs::read_dir(".").unwrap() {
-            let file_path = file.unwrap().path();
-            if file_path.is_file() && file_path.extension() == Some(OsStr::new("sus")) {
-                file_paths.push(file_path);
+    if file_paths.is_empty() && !matches.is_present("explain") {
+        let current_dir = env::current_dir().unwrap();
+        match Project::find_in(&current_dir) {
+            Ok(Some(project)) => {
+                // Build the whole project
+                file_paths = project.source_files(&config).unwrap_or_else(|err| {
+                    eprintln!("{err}");
+                    std::process::exit(1)
+                });
+                config.output_directory = Some(project.root.join(&project.manifest.output.directory));
+                config.output_layout = project.manifest.output.layout;
+                // Tops given on the command line replace the ones of the project
+                if config.top_modules.is_empty() {
+                    config.top_modules = project.manifest.top;
+                }
+                config.warnings = project.manifest.warnings;
+                config.codegen = config.format.is_none();
+                config.source_discovery = SourceDiscovery::Project(current_dir);
+            }
+            Ok(None) => {
+                // For debugging. Only a manifest or --codegen writes output files
+                log!(config, Warn, Linker, "No files given and no {MANIFEST_FILE_NAME} found, checking all .sus files in the current directory");
+                file_paths = sus_files_in_current_directory();
+                config.source_discovery = SourceDiscovery::CurrentDirectory;
+            }
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1)
             }
         }
-        config.codegen = true;
     }
 
-    file_paths
-}
-
+    // After the project manifest, so the command line overrides it
+    if let Some(output_dir) = matches.value_of("output-dir") {
+        config.output_directory = Some(PathBuf::from(output_dir));
+    }
+    match matches.value_of("layout") {
+        Some("per-module") => config.output_layout = OutputLayout::PerModule,
+        Some("per-instance") => config.output_layout = OutputLayout::PerInstance,
+        Some("single") => config.output_layout = OutputLayout::Single,
+        _ => {}
+    }
+    if matches.is_present("deny-warnings") {
+        config.warnings = WarningPolicy::Deny;
+    }
+    let mut lint_levels: Vec<(usize, DiagnosticCode, WarningPolicy)> = Vec::new();
+    for (arg, level) in [("allow", WarningPolicy::Allow), ("warn", WarningPolicy::Warn), ("deny", WarningPolicy::Deny)] {
+        if let (Some(indices), Some(codes)) = (matches.indices_of(arg), matches.values_of(arg)) {
+            for (idx, code) in indices.zip(codes) {
+                lint_levels.push((idx, DiagnosticCode::from_code_or_name(code).unwrap(), level));
+            }
+        }
+    }
+    lint_levels.sort_by_key(|(idx, _, _)| *idx);
+    config.lint_levels = lint_levels.into_iter().map(|(_, code, level)| (code, level)).collect();
+    config.explain = matches.value_of("explain").and_then(DiagnosticCode::from_code_or_name);
 
-struct ConfigStructWrapper {
-    cf: UnsafeCell<ConfigStruct>,
+    (config, file_paths)
 }
-
-unsafe impl Sync for ConfigStructWrapper {}
-
-static CONFIG: ConfigStructWrapper = ConfigStructWrapper {
-    cf: UnsafeCell::new(ConfigStruct {
-        use_lsp: false,
-        lsp_port: 25000,
-        lsp_debug_mode: false,
-        debug_print_module_contents: false,
-        codegen: false,
-        debug_print_latency_graph: false,
-        codegen_module_and_dependencies_one_file: None,
-    }),
-};
@@ -64,7 +64,7 @@ fn print_most_recent_spans(file_data: &FileData) {
         spans_to_print
     });
 
-    println!("Panic unwinding. Printing the last {} spans. BEWARE: These spans may not correspond to this file, thus incorrect spans are possible!", spans_to_print.len());
+    eprintln!("Panic unwinding. Printing the last {} spans. BEWARE: These spans may not correspond to this file, thus incorrect spans are possible!", spans_to_print.len());
     pretty_print_spans_in_reverse_order(file_data, spans_to_print);
 }
 
@@ -111,7 +111,7 @@ impl<'text> SpanDebugger<'text> {
 impl<'text> Drop for SpanDebugger<'text> {
     fn drop(&mut self) {
         if !self.defused {
-            println!("Panic happened in Span-guarded context: {}", self.context);
+            eprintln!("Panic happened in Span-guarded context: {}", self.context);
             print_most_recent_spans(self.file_data)
         }
     }
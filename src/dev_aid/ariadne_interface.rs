@@ -1,3 +1,4 @@
+use std::io::IsTerminal;
 use std::{ops::Range, path::PathBuf};
 
 use crate::compiler_top::LinkerExtraFileInfoManager;
@@ -6,8 +7,8 @@ use crate::prelude::*;
 
 use crate::{
     alloc::ArenaVector,
-    config::config,
-    errors::{CompileError, ErrorLevel},
+    config::ConfigStruct,
+    errors::{CompileError, DiagnosticCounts, ErrorLevel},
 };
 
 use ariadne::*;
@@ -67,13 +68,14 @@ impl LinkerExtraFileInfoManager for FileSourcesManager {
 }
 
 pub fn compile_all(
+    config: ConfigStruct,
     file_paths: Vec<PathBuf>,
 ) -> (Linker, FileSourcesManager) {
-    let mut linker = Linker::new();
+    let mut linker = Linker::new(config);
     let mut file_source_manager = FileSourcesManager{
         file_sources: ArenaVector::new()
     };
-    linker.add_standard_library(&mut file_source_manager);
+    linker.add_libraries(&mut file_source_manager);
 
     for file_path in file_paths {
         let file_text = match std::fs::read_to_string(&file_path) {
@@ -112,7 +114,7 @@ pub fn pretty_print_error<AriadneCache: Cache<FileUUID>>(
 
     let config = Config::default().with_index_type(IndexType::Byte);
     let mut report: ReportBuilder<'_, (FileUUID, Range<usize>)> =
-        Report::build(report_kind, file, error_span.start).with_config(config);
+        Report::build(report_kind, file, error_span.start).with_config(config).with_code(error.code.code());
     report = report.with_message(&error.reason).with_label(
         Label::new((file, error_span))
             .with_message(&error.reason)
@@ -130,19 +132,30 @@ pub fn pretty_print_error<AriadneCache: Cache<FileUUID>>(
         )
     }
 
+    for suggestion in &error.suggestions {
+        report = report.with_label(
+            Label::new((file, suggestion.position.into_range()))
+                .with_message(format!("Suggestion: {}", suggestion.description()))
+                .with_color(Color::Green),
+        )
+    }
+
     report.finish().eprint(file_cache).unwrap();
 }
 
 pub fn print_all_errors(
     linker: &Linker,
     ariadne_sources: &mut ArenaVector<Source, FileUUIDMarker>,
-) {
+) -> DiagnosticCounts {
+    let mut counts = DiagnosticCounts::default();
     let mut source_cache = (linker, ariadne_sources);
     for (file_uuid, _f) in &linker.files {
         linker.for_all_errors_in_file(file_uuid, |err| {
+            counts.add(err);
             pretty_print_error(err, file_uuid, linker, &mut source_cache);
         });
     }
+    counts
 }
 
 pub fn pretty_print_spans_in_reverse_order(file_data : &FileData, spans: Vec<Range<usize>>) {
@@ -155,7 +168,7 @@ pub fn pretty_print_spans_in_reverse_order(file_data : &FileData, spans: Vec<Ran
     for span in spans.into_iter().rev() {
         // If span not in file, just don't print it. This happens.
         if span.end > text_len {
-            println!(
+            eprintln!(
                 "Span({}, {}) certainly does not correspond to this file. ",
                 span.start, span.end
             );
@@ -164,7 +177,7 @@ pub fn pretty_print_spans_in_reverse_order(file_data : &FileData, spans: Vec<Ran
 
         let config = Config::default()
             .with_index_type(IndexType::Byte)
-            .with_color(!config().use_lsp); // Disable color because LSP output doesn't support it
+            .with_color(std::io::stderr().is_terminal()); // Disable color when logged by the LSP client, which doesn't support it
 
         let mut report: ReportBuilder<'_, Range<usize>> =
             Report::build(ReportKind::Advice, (), span.start).with_config(config);
@@ -174,7 +187,7 @@ pub fn pretty_print_spans_in_reverse_order(file_data : &FileData, spans: Vec<Ran
                 .with_color(Color::Blue),
         );
 
-        report.finish().print(&mut source).unwrap();
+        report.finish().eprint(&mut source).unwrap();
     }
 }
 
@@ -187,7 +200,7 @@ pub fn pretty_print_many_spans(file_data: &FileData, spans: &[(String, Range<usi
 
     let config = Config::default()
         .with_index_type(IndexType::Byte)
-        .with_color(!config().use_lsp); // Disable color because LSP output doesn't support it
+        .with_color(std::io::stderr().is_terminal()); // Disable color when logged by the LSP client, which doesn't support it
 
     if spans.len() == 0 {
         return;
@@ -199,7 +212,7 @@ pub fn pretty_print_many_spans(file_data: &FileData, spans: &[(String, Range<usi
     for (text, span) in spans.into_iter().rev() {
         // If span not in file, just don't print it. This happens.
         if span.end > text_len {
-            println!(
+            eprintln!(
                 "Span({}, {}) certainly does not correspond to this file. ",
                 span.start, span.end
             );
@@ -212,5 +225,5 @@ pub fn pretty_print_many_spans(file_data: &FileData, spans: &[(String, Range<usi
                 .with_color(Color::Blue),
         );
     }
-    report.finish().print(&mut source).unwrap();
+    report.finish().eprint(&mut source).unwrap();
 }
@@ -0,0 +1,369 @@
+use std::ops::Range;
+
+use sus_proc_macro::{kind, kw};
+use tree_sitter::{Node, Parser, Tree, TreeCursor};
+
+/// The indentation of one nesting level
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Indent {
+    Tabs,
+    Spaces(usize),
+}
+
+impl Indent {
+    /// Keeps the indentation the file already uses, that of its first indented line. Files without any get tabs, like the standard library
+    pub fn detect(text: &str) -> Indent {
+        let first_indented = text.lines().find_map(|line| {
+            let content = line.trim_start_matches([' ', '\t']);
+            // The continuation lines of block comments are often indented by a single space
+            let is_code = !content.is_empty() && !content.starts_with('*');
+            (is_code && content.len() != line.len()).then(|| &line[..line.len() - content.len()])
+        });
+        match first_indented {
+            Some(whitespace) if !whitespace.starts_with('\t') => {
+                Indent::Spaces(whitespace.chars().take_while(|c| *c == ' ').count())
+            }
+            _ => Indent::Tabs,
+        }
+    }
+
+    fn repeat(self, levels: usize) -> String {
+        match self {
+            Indent::Tabs => "\t".repeat(levels),
+            Indent::Spaces(width) => " ".repeat(width * levels),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TokenRole {
+    Normal,
+    /// Everything in a port declaration before the name: modifiers and type
+    PortDeclPrefix(usize),
+    PortDeclName(usize),
+    PortDeclLatency(usize),
+}
+
+struct Token<'t> {
+    text: &'t str,
+    range: Range<usize>,
+    role: TokenRole,
+    newlines_before: usize,
+    space_before: bool,
+}
+
+/// One line of formatted output. Every line corresponds to exactly one line of the original text,
+/// because the formatter keeps line breaks where they were, only collapsing blank lines
+pub struct FormattedLine {
+    /// From the start of the original line up to its end, excluding the newline
+    pub original: Range<usize>,
+    pub text: String,
+    pub blank_line_before: bool,
+}
+
+fn is_port_declaration(node: Node) -> bool {
+    node.kind_id() == kind!("declaration")
+        && node.parent().map_or(false, |list| {
+            list.kind_id() == kind!("declaration_list")
+                && list.parent().map_or(false, |ports| ports.kind_id() == kind!("interface_ports"))
+        })
+}
+
+fn collect_tokens<'t>(text: &'t str, cursor: &mut TreeCursor, role: TokenRole, tokens: &mut Vec<Token<'t>>) {
+    let node = cursor.node();
+    if node.child_count() == 0 {
+        let range = node.byte_range();
+        // Line breaks are tokens in the grammar, but here they are kept through [Token::newlines_before]
+        if !range.is_empty() && node.kind_id() != kw!("\n") {
+            tokens.push(Token {
+                text: &text[range.clone()],
+                range,
+                role,
+                newlines_before: 0,
+                space_before: false,
+            });
+        }
+        return;
+    }
+
+    let port_decl = is_port_declaration(node).then(|| node.id());
+    cursor.goto_first_child();
+    loop {
+        let child_role = match port_decl {
+            Some(decl) => match cursor.field_name() {
+                Some("name") => TokenRole::PortDeclName(decl),
+                Some("latency_specifier") => TokenRole::PortDeclLatency(decl),
+                _ => TokenRole::PortDeclPrefix(decl),
+            },
+            None => role,
+        };
+        collect_tokens(text, cursor, child_role, tokens);
+        if !cursor.goto_next_sibling() {
+            break;
+        }
+    }
+    cursor.goto_parent();
+}
+
+/// Returns None if there's anything but whitespace between the tokens, which would be lost when formatting
+fn tokenize<'t>(text: &'t str, tree: &Tree) -> Option<Vec<Token<'t>>> {
+    let mut tokens = Vec::new();
+    collect_tokens(text, &mut tree.walk(), TokenRole::Normal, &mut tokens);
+
+    let mut prev_end = 0;
+    for tok in &mut tokens {
+        let gap = &text[prev_end..tok.range.start];
+        if !gap.chars().all(char::is_whitespace) {
+            return None;
+        }
+        tok.newlines_before = gap.matches('\n').count();
+        tok.space_before = !gap.is_empty();
+        prev_end = tok.range.end;
+    }
+    if !text[prev_end..].chars().all(char::is_whitespace) {
+        return None;
+    }
+    Some(tokens)
+}
+
+fn render_tokens(tokens: &[Token], skip_first_space: bool) -> String {
+    let mut result = String::new();
+    for (i, tok) in tokens.iter().enumerate() {
+        if tok.space_before && !(i == 0 && skip_first_space) {
+            result.push(' ');
+        }
+        result.push_str(tok.text);
+    }
+    result
+}
+
+/// The parts of a line consisting of a single port declaration, for alignment
+struct PortDeclLine<'l, 't> {
+    prefix: &'l [Token<'t>],
+    name: &'l Token<'t>,
+    latency: &'l [Token<'t>],
+    rest: &'l [Token<'t>],
+}
+
+fn split_port_decl_line<'l, 't>(line: &'l [Token<'t>]) -> Option<PortDeclLine<'l, 't>> {
+    let TokenRole::PortDeclPrefix(decl) = line.first()?.role else {
+        return None;
+    };
+    let name_idx = line.iter().position(|tok| tok.role != TokenRole::PortDeclPrefix(decl))?;
+    if line[name_idx].role != TokenRole::PortDeclName(decl) {
+        return None;
+    }
+    let latency_end = line[name_idx + 1..]
+        .iter()
+        .position(|tok| tok.role != TokenRole::PortDeclLatency(decl))
+        .map_or(line.len(), |p| p + name_idx + 1);
+    let rest = &line[latency_end..];
+    // Another declaration on the same line can't be aligned
+    if rest.iter().any(|tok| tok.role != TokenRole::Normal) {
+        return None;
+    }
+    Some(PortDeclLine {
+        prefix: &line[..name_idx],
+        name: &line[name_idx],
+        latency: &line[name_idx + 1..latency_end],
+        rest,
+    })
+}
+
+fn align_port_decl_group(group: &[PortDeclLine], indent: &str) -> Vec<String> {
+    let prefix_strs: Vec<String> = group.iter().map(|l| render_tokens(l.prefix, true)).collect();
+    let prefix_width = prefix_strs.iter().map(|s| s.chars().count()).max().unwrap_or(0);
+    let name_width = group.iter().map(|l| l.name.text.chars().count()).max().unwrap_or(0);
+
+    group
+        .iter()
+        .zip(prefix_strs)
+        .map(|(line, prefix)| {
+            let mut result = format!("{indent}{prefix:<prefix_width$} {}", line.name.text);
+            if !line.latency.is_empty() {
+                let padding = name_width - line.name.text.chars().count();
+                result.push_str(&" ".repeat(padding));
+                result.push_str(&render_tokens(line.latency, true));
+            }
+            result.push_str(&render_tokens(line.rest, false));
+            result
+        })
+        .collect()
+}
+
+fn is_opening(tok: &Token) -> bool {
+    matches!(tok.text, "{" | "(" | "[")
+}
+fn is_closing(tok: &Token) -> bool {
+    matches!(tok.text, "}" | ")" | "]")
+}
+
+/// Formats the file from its syntax tree: keeps all comments and line breaks, collapses blank lines,
+/// collapses whitespace within lines, reindents according to bracket nesting and aligns multi-line `interface` port lists.
+///
+/// Returns None if the file has syntax errors
+pub fn format_lines(text: &str, tree: &Tree, indent: Indent) -> Option<Vec<FormattedLine>> {
+    if tree.root_node().has_error() {
+        return None;
+    }
+    let tokens = tokenize(text, tree)?;
+
+    let mut lines: Vec<&[Token]> = Vec::new();
+    let mut line_start = 0;
+    for i in 1..=tokens.len() {
+        if i == tokens.len() || tokens[i].newlines_before > 0 {
+            lines.push(&tokens[line_start..i]);
+            line_start = i;
+        }
+    }
+
+    let mut indents = Vec::with_capacity(lines.len());
+    let mut bracket_stack: Vec<usize> = Vec::new();
+    for line in &lines {
+        let indent = if is_closing(&line[0]) {
+            bracket_stack.last().copied().unwrap_or(0)
+        } else {
+            bracket_stack.last().map_or(0, |opened_at| opened_at + 1)
+        };
+        for tok in line.iter() {
+            if is_opening(tok) {
+                bracket_stack.push(indent);
+            } else if is_closing(tok) {
+                bracket_stack.pop();
+            }
+        }
+        indents.push(indent);
+    }
+
+    let mut result: Vec<FormattedLine> = Vec::with_capacity(lines.len());
+    let mut i = 0;
+    while i < lines.len() {
+        let indent_str = indent.repeat(indents[i]);
+
+        let mut group = Vec::new();
+        while let Some(port_decl) = lines.get(i + group.len()).and_then(|line| split_port_decl_line(line)) {
+            let line_idx = i + group.len();
+            if !group.is_empty() && (indents[line_idx] != indents[i] || lines[line_idx][0].newlines_before > 1) {
+                break;
+            }
+            group.push(port_decl);
+        }
+
+        let texts = if group.len() >= 2 {
+            align_port_decl_group(&group, &indent_str)
+        } else {
+            vec![format!("{indent_str}{}", render_tokens(lines[i], true))]
+        };
+
+        for text_line in texts {
+            let line = lines[i];
+            let first = &line[0];
+            let last = &line[line.len() - 1];
+            let start = text[..first.range.start].rfind('\n').map_or(0, |p| p + 1);
+            let end = text[last.range.end..].find('\n').map_or(text.len(), |p| last.range.end + p);
+            result.push(FormattedLine {
+                original: start..end,
+                text: text_line,
+                blank_line_before: !result.is_empty() && first.newlines_before > 1,
+            });
+            i += 1;
+        }
+    }
+    Some(result)
+}
+
+fn parse(text: &str) -> Tree {
+    let mut parser = Parser::new();
+    parser.set_language(&tree_sitter_sus::language()).unwrap();
+    parser.parse(text, None).unwrap()
+}
+
+/// Formatting must never change the meaning of the code, so check that the same tokens come out
+fn same_tokens(original: &str, original_tree: &Tree, formatted: &str) -> bool {
+    let formatted_tree = parse(formatted);
+    if formatted_tree.root_node().has_error() {
+        return false;
+    }
+    let (Some(a), Some(b)) = (tokenize(original, original_tree), tokenize(formatted, &formatted_tree)) else {
+        return false;
+    };
+    a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| x.text == y.text)
+}
+
+/// The whole file formatted. Returns None if the file has syntax errors
+pub fn format_file(text: &str, tree: &Tree, indent: Indent) -> Option<String> {
+    let lines = format_lines(text, tree, indent)?;
+
+    let mut result = String::with_capacity(text.len());
+    for line in &lines {
+        if line.blank_line_before {
+            result.push('\n');
+        }
+        result.push_str(&line.text);
+        result.push('\n');
+    }
+
+    same_tokens(text, tree, &result).then_some(result)
+}
+
+/// Replacements for the lines that overlap the given byte range. Blank lines are left as they are
+pub fn format_range(text: &str, tree: &Tree, range: Range<usize>, indent: Indent) -> Option<Vec<(Range<usize>, String)>> {
+    // Make sure the file as a whole formats correctly, or don't touch it at all
+    format_file(text, tree, indent)?;
+    let lines = format_lines(text, tree, indent)?;
+
+    Some(
+        lines
+            .into_iter()
+            .filter(|line| line.original.start <= range.end && range.start <= line.original.end)
+            .filter(|line| text[line.original.clone()] != line.text)
+            .map(|line| (line.original, line.text))
+            .collect(),
+    )
+}
+
+/// For formatting files that aren't loaded into a [crate::linker::Linker], such as with `sus fmt`. See [Indent::detect]
+pub fn parse_and_format(text: &str) -> Option<String> {
+    format_file(text, &parse(text), Indent::detect(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNFORMATTED: &str = "module Add {\ninterface Add :   int a, int b -> int c\n\n\n    c = a +   b\n}\n";
+
+    #[test]
+    fn detect_indent() {
+        assert_eq!(Indent::detect("module A {\n\tint x\n}\n"), Indent::Tabs);
+        assert_eq!(Indent::detect("module A {\n  int x\n}\n"), Indent::Spaces(2));
+        assert_eq!(Indent::detect("/*\n * doc\n */\nmodule A {\n    int x\n}\n"), Indent::Spaces(4));
+        assert_eq!(Indent::detect("module A {}\n"), Indent::Tabs);
+    }
+
+    #[test]
+    fn format_round_trip() {
+        let formatted = format_file(UNFORMATTED, &parse(UNFORMATTED), Indent::Tabs).unwrap();
+        assert_eq!(formatted, "module Add {\n\tinterface Add : int a, int b -> int c\n\n\tc = a + b\n}\n");
+        // Formatting is idempotent, and keeps the indentation it detects
+        assert_eq!(parse_and_format(&formatted).unwrap(), formatted);
+
+        let with_spaces = format_file(UNFORMATTED, &parse(UNFORMATTED), Indent::Spaces(4)).unwrap();
+        assert_eq!(with_spaces, formatted.replace('\t', "    "));
+        assert_eq!(parse_and_format(&with_spaces).unwrap(), with_spaces);
+    }
+
+    #[test]
+    fn syntax_errors_are_not_formatted() {
+        assert_eq!(parse_and_format("module A {\n\tint x = \n"), None);
+    }
+
+    #[test]
+    fn format_range_only_touches_overlapping_lines() {
+        let text = "module A {\nint x\n}\nmodule B {\nint y\n}\n";
+        let tree = parse(text);
+        let range_start = text.find("int y").unwrap();
+        let edits = format_range(text, &tree, range_start..range_start, Indent::Tabs).unwrap();
+        assert_eq!(edits, vec![(range_start..range_start + "int y".len(), "\tint y".to_owned())]);
+    }
+}
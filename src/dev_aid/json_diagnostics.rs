@@ -0,0 +1,106 @@
+use crate::prelude::*;
+
+use serde_json::{json, Value as JsonValue};
+
+use crate::errors::{CompileError, DiagnosticCounts, ErrorLevel};
+use crate::file_position::FileText;
+
+fn json_range(file_text: &FileText, span: Span) -> JsonValue {
+    let range = file_text.get_span_linecol_range(span);
+    json!({
+        "byteStart": span.into_range().start,
+        "byteEnd": span.into_range().end,
+        "start": {"line": range.start.line, "column": range.start.col},
+        "end": {"line": range.end.line, "column": range.end.col},
+    })
+}
+
+/// One diagnostic, in the format of `--message-format json`. Lines and columns are 0-based, columns count characters
+///
+/// `{ level, code, message, file, range: { byteStart, byteEnd, start: { line, column }, end }, instance, infos: [{ message, file, range }], suggestions: [{ replaceWith, range }] }`
+pub fn error_to_json(linker: &Linker, file: FileUUID, error: &CompileError, instance_name: Option<&str>) -> JsonValue {
+    let file_data = &linker.files[file];
+    let infos: Vec<JsonValue> = error
+        .infos
+        .iter()
+        .map(|info| {
+            let info_file = &linker.files[info.file];
+            json!({
+                "message": info.info,
+                "file": info_file.file_identifier,
+                "range": json_range(&info_file.file_text, info.position),
+            })
+        })
+        .collect();
+    let suggestions: Vec<JsonValue> = error
+        .suggestions
+        .iter()
+        .map(|suggestion| {
+            json!({
+                "replaceWith": suggestion.replace_with,
+                "range": json_range(&file_data.file_text, suggestion.position),
+            })
+        })
+        .collect();
+
+    json!({
+        "level": match error.level {
+            ErrorLevel::Error => "error",
+            ErrorLevel::Warning => "warning",
+        },
+        "code": error.code.code(),
+        "message": error.reason,
+        "file": file_data.file_identifier,
+        "range": json_range(&file_data.file_text, error.position),
+        "instance": instance_name,
+        "infos": infos,
+        "suggestions": suggestions,
+    })
+}
+
+/// Prints every error as a JSON object on its own line on stdout, for CI and other tools
+pub fn print_all_errors_json(linker: &Linker) -> DiagnosticCounts {
+    let mut counts = DiagnosticCounts::default();
+    for (file_uuid, _f) in &linker.files {
+        linker.for_all_errors_in_file_with_instance(file_uuid, |err, instance_name| {
+            counts.add(err);
+            println!("{}", error_to_json(linker, file_uuid, err, instance_name));
+        });
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler_top::test_util::compile;
+
+    #[test]
+    fn unknown_name_as_json() {
+        let (linker, file_id) = compile("module M {\n    int a = b\n}\n");
+        let mut diagnostics = Vec::new();
+        linker.for_all_errors_in_file_with_instance(file_id, |err, instance_name| {
+            if err.code.code() == "E0003" {
+                diagnostics.push(error_to_json(&linker, file_id, err, instance_name));
+            }
+        });
+        assert_eq!(
+            diagnostics,
+            vec![json!({
+                "level": "error",
+                "code": "E0003",
+                "message": "No Global of the name 'b' was found. Did you forget to import it?",
+                "file": "test.sus",
+                "range": {
+                    "byteStart": 23,
+                    "byteEnd": 24,
+                    "start": {"line": 1, "column": 12},
+                    "end": {"line": 1, "column": 13},
+                },
+                "instance": null,
+                "infos": [],
+                "suggestions": [],
+            })]
+        );
+    }
+}
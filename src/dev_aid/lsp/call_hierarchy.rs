@@ -0,0 +1,170 @@
+use std::rc::Rc;
+
+use crate::prelude::*;
+
+use lsp_types::{CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, SymbolKind};
+
+use crate::flattening::Instruction;
+use crate::instantiation::InstantiatedModule;
+use crate::linker::NameElem;
+
+use super::tree_walk::{get_selected_object, InModule, LocationInfo};
+use super::{file_uri, span_to_lsp_range};
+
+/// Items either refer to a module in general, or to one of its concrete instances.
+/// The instance is identified by its unique name, as instances are recreated on every recompile
+fn module_item(linker: &Linker, md_id: ModuleUUID, instance: Option<&InstantiatedModule>) -> Option<CallHierarchyItem> {
+    let md = &linker.modules[md_id];
+    let file_data = &linker.files[md.link_info.file];
+    Some(CallHierarchyItem {
+        name: md.link_info.name.clone(),
+        kind: SymbolKind::MODULE,
+        tags: None,
+        detail: instance.map(|inst| inst.name.clone()),
+        uri: file_uri(file_data)?,
+        range: span_to_lsp_range(&file_data.file_text, md.link_info.span),
+        selection_range: span_to_lsp_range(&file_data.file_text, md.link_info.name_span),
+        data: Some(serde_json::json!({
+            "module": md_id.get_hidden_value(),
+            "instance": instance.map(|inst| inst.name.clone()),
+        })),
+    })
+}
+
+/// Finds the module and instance an item refers to. Returns None if the module has since been removed
+fn item_target(linker: &Linker, item: &CallHierarchyItem) -> Option<(ModuleUUID, Option<Rc<InstantiatedModule>>)> {
+    let data = item.data.as_ref()?;
+    let md_id = ModuleUUID::from_hidden_value(data["module"].as_u64()? as usize);
+    let md = linker.modules.get(md_id)?;
+    if md.link_info.name != item.name {
+        return None;
+    }
+    let Some(instance_name) = data["instance"].as_str() else {
+        return Some((md_id, None));
+    };
+    let mut found = None;
+    md.instantiations.for_each_instance(|_template_args, inst| {
+        if inst.name == instance_name {
+            found = Some(inst.clone());
+        }
+    });
+    Some((md_id, Some(found?)))
+}
+
+/// The module that is selected, together with all of its concrete instances
+pub fn prepare_call_hierarchy(linker: &Linker, file_id: FileUUID, position: usize) -> Vec<CallHierarchyItem> {
+    let md_id = match get_selected_object(linker, file_id, position) {
+        Some((_span, LocationInfo::Global(NameElem::Module(md_id)))) => md_id,
+        Some((_span, LocationInfo::InModule(_, _, _, InModule::NamedSubmodule(sm)))) => sm.module_ref.id,
+        _ => return Vec::new(),
+    };
+
+    let mut result: Vec<CallHierarchyItem> = module_item(linker, md_id, None).into_iter().collect();
+    linker.modules[md_id].instantiations.for_each_instance(|_template_args, inst| {
+        result.extend(module_item(linker, md_id, Some(inst)));
+    });
+    result
+}
+
+fn submodule_instruction_span(linker: &Linker, md_id: ModuleUUID, submodule_instr: FlatID) -> lsp_types::Range {
+    let md = &linker.modules[md_id];
+    let sm = md.instructions[submodule_instr].unwrap_submodule();
+    let span = match &sm.name {
+        Some((_name, name_span)) => Span::new_overarching(sm.module_ref.span, *name_span),
+        None => sm.module_ref.span,
+    };
+    span_to_lsp_range(&linker.files[md.link_info.file].file_text, span)
+}
+
+/// All modules that instantiate this module. For a concrete instance, all concrete instances containing it
+pub fn incoming_calls(linker: &Linker, item: &CallHierarchyItem) -> Vec<CallHierarchyIncomingCall> {
+    let Some((target_id, target_instance)) = item_target(linker, item) else {
+        return Vec::new();
+    };
+
+    let mut result = Vec::new();
+    for (parent_id, parent) in &linker.modules {
+        match &target_instance {
+            None => {
+                let from_ranges: Vec<lsp_types::Range> = parent
+                    .instructions
+                    .iter()
+                    .filter_map(|(id, instr)| match instr {
+                        Instruction::SubModule(sm) if sm.module_ref.id == target_id => {
+                            Some(submodule_instruction_span(linker, parent_id, id))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                if from_ranges.is_empty() {
+                    continue;
+                }
+                if let Some(from) = module_item(linker, parent_id, None) {
+                    result.push(CallHierarchyIncomingCall { from, from_ranges });
+                }
+            }
+            Some(target_inst) => {
+                parent.instantiations.for_each_instance(|_template_args, parent_inst| {
+                    let from_ranges: Vec<lsp_types::Range> = parent_inst
+                        .submodules
+                        .iter()
+                        .filter(|(_id, sm)| sm.instance.as_ref().map_or(false, |inst| Rc::ptr_eq(inst, target_inst)))
+                        .map(|(_id, sm)| submodule_instruction_span(linker, parent_id, sm.original_instruction))
+                        .collect();
+                    if from_ranges.is_empty() {
+                        return;
+                    }
+                    if let Some(from) = module_item(linker, parent_id, Some(parent_inst)) {
+                        result.push(CallHierarchyIncomingCall { from, from_ranges });
+                    }
+                });
+            }
+        }
+    }
+    result
+}
+
+/// All submodules of this module. For a concrete instance, the concrete instances of its submodules,
+/// so the whole instantiated tree can be explored
+pub fn outgoing_calls(linker: &Linker, item: &CallHierarchyItem) -> Vec<CallHierarchyOutgoingCall> {
+    let Some((md_id, instance)) = item_target(linker, item) else {
+        return Vec::new();
+    };
+
+    let mut result = Vec::new();
+    match instance {
+        None => {
+            let mut per_submodule: Vec<(ModuleUUID, Vec<lsp_types::Range>)> = Vec::new();
+            for (id, instr) in &linker.modules[md_id].instructions {
+                let Instruction::SubModule(sm) = instr else {
+                    continue;
+                };
+                let range = submodule_instruction_span(linker, md_id, id);
+                match per_submodule.iter_mut().find(|(sm_md, _)| *sm_md == sm.module_ref.id) {
+                    Some((_, ranges)) => ranges.push(range),
+                    None => per_submodule.push((sm.module_ref.id, vec![range])),
+                }
+            }
+            for (sm_md, from_ranges) in per_submodule {
+                if let Some(to) = module_item(linker, sm_md, None) {
+                    result.push(CallHierarchyOutgoingCall { to, from_ranges });
+                }
+            }
+        }
+        Some(inst) => {
+            for (_id, sm) in &inst.submodules {
+                // Submodules that failed to instantiate have no tree to explore
+                let Some(sm_inst) = &sm.instance else {
+                    continue;
+                };
+                if let Some(to) = module_item(linker, sm.module_uuid, Some(sm_inst)) {
+                    result.push(CallHierarchyOutgoingCall {
+                        to,
+                        from_ranges: vec![submodule_instruction_span(linker, md_id, sm.original_instruction)],
+                    });
+                }
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,231 @@
+use crate::prelude::*;
+
+use lsp_types::{DocumentSymbol, FoldingRange, FoldingRangeKind, Location, SymbolInformation, SymbolKind};
+use sus_proc_macro::kind;
+
+use crate::file_position::FileText;
+use crate::flattening::{Declaration, DeclarationPortInfo, Instruction, Module};
+use crate::typing::abstract_type::DomainType;
+use crate::linker::{FileData, NameElem};
+
+use super::{file_uri, span_to_lsp_range};
+
+#[allow(deprecated)]
+fn make_symbol(
+    file_text: &FileText,
+    name: String,
+    detail: Option<String>,
+    kind: SymbolKind,
+    span: Span,
+    name_span: Span,
+    children: Option<Vec<DocumentSymbol>>,
+) -> DocumentSymbol {
+    DocumentSymbol {
+        name,
+        detail,
+        kind,
+        tags: None,
+        deprecated: None,
+        range: span_to_lsp_range(file_text, span),
+        selection_range: span_to_lsp_range(file_text, name_span),
+        children,
+    }
+}
+
+/// Ports belong to the interface that contains them, other declarations to the first interface of their domain
+fn interface_of(md: &Module, decl: &Declaration) -> Option<InterfaceID> {
+    if let DeclarationPortInfo::RegularPort { is_input: _, port_id } = decl.is_port {
+        return md.interfaces.iter().find(|(_, interface)| interface.all_ports().contains(port_id)).map(|(id, _)| id);
+    }
+    let DomainType::Physical(domain) = decl.typ.domain else {
+        return None;
+    };
+    md.interfaces.iter().find(|(_, interface)| interface.domain == domain).map(|(id, _)| id)
+}
+
+fn module_children(md: &Module, file_text: &FileText) -> Vec<DocumentSymbol> {
+    let mut children = Vec::new();
+    let mut interface_children: FlatAlloc<Vec<(Span, DocumentSymbol)>, InterfaceIDMarker> = md.interfaces.map(|_| Vec::new());
+
+    for (_id, instr) in &md.instructions {
+        match instr {
+            Instruction::Declaration(decl) => {
+                let (kind, detail) = match decl.is_port {
+                    DeclarationPortInfo::RegularPort { is_input, port_id: _ } => {
+                        (SymbolKind::FIELD, Some(if is_input { "input" } else { "output" }))
+                    }
+                    DeclarationPortInfo::GenerativeInput(_) => (SymbolKind::TYPE_PARAMETER, Some("input gen")),
+                    DeclarationPortInfo::Crossed { from: _, group_leader: _ } => (SymbolKind::VARIABLE, Some("cross")),
+                    DeclarationPortInfo::NotPort | DeclarationPortInfo::StructField { field_id: _ } => {
+                        if decl.identifier_type.is_generative() {
+                            (SymbolKind::CONSTANT, Some("gen"))
+                        } else {
+                            (SymbolKind::VARIABLE, None)
+                        }
+                    }
+                };
+                let symbol = make_symbol(
+                    file_text,
+                    decl.name.clone(),
+                    detail.map(str::to_owned),
+                    kind,
+                    decl.decl_span,
+                    decl.name_span,
+                    None,
+                );
+                match interface_of(md, decl) {
+                    Some(interface_id) => interface_children[interface_id].push((decl.decl_span, symbol)),
+                    None => children.push(symbol),
+                }
+            }
+            Instruction::SubModule(sm) => {
+                let Some((name, name_span)) = &sm.name else {
+                    continue; // Inline function calls don't show up in the outline
+                };
+                children.push(make_symbol(
+                    file_text,
+                    name.clone(),
+                    Some(file_text[sm.module_ref.span].to_owned()),
+                    SymbolKind::OBJECT,
+                    Span::new_overarching(sm.module_ref.span, *name_span),
+                    *name_span,
+                    None,
+                ));
+            }
+            Instruction::FuncCall(_)
+            | Instruction::Wire(_)
+            | Instruction::Write(_)
+            | Instruction::IfStatement(_)
+            | Instruction::ForStatement(_) => {}
+        }
+    }
+
+    // The first domain is implicitly named after the module itself
+    let mut header = Vec::new();
+    for (id, name) in md.domain_names.iter().skip(1) {
+        let span = md.domain_name_spans[id];
+        header.push(make_symbol(file_text, name.clone(), Some("domain".to_owned()), SymbolKind::NAMESPACE, span, span, None));
+    }
+    for (id, interface) in &md.interfaces {
+        let nested = std::mem::take(&mut interface_children[id]);
+        // An editor expects the range of a symbol to cover those of its children
+        let range = nested.iter().fold(interface.name_span.into_range(), |range, (span, _)| {
+            let span = span.into_range();
+            range.start.min(span.start)..range.end.max(span.end)
+        });
+        header.push(make_symbol(
+            file_text,
+            interface.name.clone(),
+            None,
+            SymbolKind::INTERFACE,
+            Span::from(range),
+            interface.name_span,
+            Some(nested.into_iter().map(|(_, symbol)| symbol).collect()),
+        ));
+    }
+    header.extend(children);
+    header
+}
+
+/// The outline of the file: all globals, and for modules their domains, interfaces with their ports and declarations, and submodules
+pub fn make_document_symbols(linker: &Linker, file_id: FileUUID) -> Vec<DocumentSymbol> {
+    let file_data = &linker.files[file_id];
+    let file_text = &file_data.file_text;
+
+    let mut result = Vec::new();
+    for global in &file_data.associated_values {
+        match *global {
+            NameElem::Module(md_id) => {
+                let md = &linker.modules[md_id];
+                result.push(make_symbol(
+                    file_text,
+                    md.link_info.name.clone(),
+                    None,
+                    SymbolKind::MODULE,
+                    md.link_info.span,
+                    md.link_info.name_span,
+                    Some(module_children(md, file_text)),
+                ));
+            }
+            NameElem::Type(typ_id) => {
+                let typ = &linker.types[typ_id];
+                result.push(make_symbol(
+                    file_text,
+                    typ.link_info.name.clone(),
+                    None,
+                    SymbolKind::STRUCT,
+                    typ.link_info.span,
+                    typ.link_info.name_span,
+                    None,
+                ));
+            }
+            NameElem::Constant(_) => {} // Constants don't have LinkInfo yet
+        }
+    }
+    result
+}
+
+/// All modules and types whose name contains the query, ignoring case
+#[allow(deprecated)]
+pub fn make_workspace_symbols(linker: &Linker, query: &str) -> Vec<SymbolInformation> {
+    let query = query.to_lowercase();
+
+    let modules = linker
+        .modules
+        .iter()
+        .map(|(_id, md)| (&md.link_info, SymbolKind::MODULE));
+    let types = linker
+        .types
+        .iter()
+        .map(|(_id, typ)| (&typ.link_info, SymbolKind::STRUCT));
+
+    modules
+        .chain(types)
+        .filter(|(link_info, _kind)| link_info.name.to_lowercase().contains(&query))
+        .filter_map(|(link_info, kind)| {
+            let file_data = &linker.files[link_info.file];
+            let uri = file_uri(file_data)?;
+            Some(SymbolInformation {
+                name: link_info.name.clone(),
+                kind,
+                tags: None,
+                deprecated: None,
+                location: Location {
+                    uri,
+                    range: span_to_lsp_range(&file_data.file_text, link_info.span),
+                },
+                container_name: None,
+            })
+        })
+        .collect()
+}
+
+/// Every multi-line `block` node in the syntax tree can be folded
+pub fn make_folding_ranges(file_data: &FileData) -> Vec<FoldingRange> {
+    let mut result = Vec::new();
+
+    let mut cursor = file_data.tree.walk();
+    loop {
+        let node = cursor.node();
+        if node.kind_id() == kind!("block") {
+            let start_line = node.start_position().row as u32;
+            let end_line = node.end_position().row as u32;
+            if end_line > start_line {
+                result.push(FoldingRange {
+                    start_line,
+                    end_line,
+                    kind: Some(FoldingRangeKind::Region),
+                    ..Default::default()
+                });
+            }
+        }
+        if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                return result;
+            }
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use crate::prelude::*;
+
+use serde_json::{json, Value as JsonValue};
+
+use crate::codegen_fallback::gen_verilog_code_with_source_map;
+use crate::file_position::FileText;
+use crate::linker::NameElem;
+use crate::typing::template::ConcreteTemplateArg;
+
+use super::tree_walk::{get_selected_object, InModule, LocationInfo};
+use super::span_to_lsp_range;
+
+/// Custom request: `sus/generatedCode`
+///
+/// Params: `{ textDocument: { uri }, position?: Position, module?: string, templateArgs?: { [name]: string } }`.
+/// The module is either given by name, or is the module selected at, or surrounding, the position.
+/// `templateArgs` restricts the result to the instances whose template arguments print as the given values.
+///
+/// Result: `[{ name, code, sourceMap: [{ generatedRange: Range, sourceRange: Range }] }]`, one entry per cached instance.
+/// `generatedRange` is a range in `code`, `sourceRange` a range in the file the module is defined in.
+pub const GENERATED_CODE_METHOD: &str = "sus/generatedCode";
+
+fn find_requested_module(linker: &Linker, file_id: FileUUID, params: &JsonValue) -> Option<ModuleUUID> {
+    if let Some(module_name) = params["module"].as_str() {
+        return linker
+            .modules
+            .iter()
+            .find(|(_id, md)| md.link_info.name == module_name)
+            .map(|(id, _md)| id);
+    }
+
+    let file_data = &linker.files[file_id];
+    let position: lsp_types::Position = serde_json::from_value(params["position"].clone()).ok()?;
+    let position = file_data.file_text.utf16_linecol_to_byte_clamp(super::from_position(position));
+    match get_selected_object(linker, file_id, position) {
+        Some((_span, LocationInfo::Global(NameElem::Module(md_id)))) => Some(md_id),
+        Some((_span, LocationInfo::InModule(_, _, _, InModule::NamedSubmodule(sm)))) => Some(sm.module_ref.id),
+        _ => linker
+            .modules
+            .iter()
+            .find(|(_id, md)| md.link_info.file == file_id && md.link_info.span.contains_pos(position))
+            .map(|(id, _md)| id),
+    }
+}
+
+pub fn generated_code(linker: &Linker, file_id: FileUUID, params: &JsonValue) -> JsonValue {
+    let Some(md_id) = find_requested_module(linker, file_id, params) else {
+        return json!([]);
+    };
+    let md = &linker.modules[md_id];
+    let source_file_text = &linker.files[md.link_info.file].file_text;
+    let requested_args = params["templateArgs"].as_object();
+
+    let mut result = Vec::new();
+    md.instantiations.for_each_instance(|template_args, inst| {
+        if let Some(requested_args) = requested_args {
+            let matches_request = md.link_info.template_arguments.iter().all(|(id, input)| {
+                let Some(requested) = requested_args.get(&input.name) else {
+                    return true;
+                };
+                let given = match &template_args[id] {
+                    ConcreteTemplateArg::Type(t) => t.to_string(&linker.types),
+                    ConcreteTemplateArg::Value(v) => v.value.to_string(),
+                    ConcreteTemplateArg::NotProvided => return false,
+                };
+                requested.as_str() == Some(given.as_str())
+            });
+            if !matches_request {
+                return;
+            }
+        }
+        // Instances with errors can't be generated
+        if inst.errors.did_error {
+            return;
+        }
+
+        let generated = gen_verilog_code_with_source_map(linker, md, inst, true);
+        let generated_file_text = FileText::new(generated.code);
+        let source_map: Vec<JsonValue> = generated
+            .source_map
+            .into_iter()
+            .map(|(generated_range, source_span)| {
+                json!({
+                    "generatedRange": span_to_lsp_range(&generated_file_text, Span::from(generated_range)),
+                    "sourceRange": span_to_lsp_range(source_file_text, source_span),
+                })
+            })
+            .collect();
+        result.push(json!({
+            "name": inst.name,
+            "code": generated_file_text.file_text,
+            "sourceMap": source_map,
+        }));
+    });
+    JsonValue::Array(result)
+}
@@ -102,6 +102,7 @@ pub fn hover(info: LocationInfo, linker: &Linker, file_data: &FileData) -> Vec<M
                     port_id: _,
                 } => details_vec.push(if is_input { "input" } else { "output" }),
                 DeclarationPortInfo::NotPort | DeclarationPortInfo::StructField { field_id:_ } => {}
+                DeclarationPortInfo::Crossed { from:_, group_leader:_ } => details_vec.push("cross"),
                 DeclarationPortInfo::GenerativeInput(_) => details_vec.push("input"), // "gen" in "input gen" is covered by decl.identifier_type
             }
 
@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::prelude::*;
+
+use lsp_types::{InlayHint, InlayHintKind, InlayHintLabel};
+
+use crate::flattening::{Instruction, Module, WireReferenceRoot};
+use crate::instantiation::{InstantiatedModule, SubModuleOrWire, CALCULATE_LATENCY_LATER};
+use crate::linker::NameElem;
+
+use super::to_position;
+
+/// Which wire of an instance a declaration or write target refers to
+#[derive(Clone, Copy)]
+enum HintedWire {
+    Local(FlatID),
+    SubModulePort(FlatID, PortID),
+}
+
+impl HintedWire {
+    fn latency_in(&self, inst: &InstantiatedModule) -> Option<i64> {
+        let wire_id = match *self {
+            HintedWire::Local(decl_id) => match &inst.generation_state[decl_id] {
+                SubModuleOrWire::Wire(w) => *w,
+                _ => return None,
+            },
+            HintedWire::SubModulePort(submod_decl, port) => match &inst.generation_state[submod_decl] {
+                SubModuleOrWire::SubModule(sm) => inst.submodules[*sm].port_map[port].as_ref()?.maps_to_wire,
+                _ => return None,
+            },
+        };
+        let latency = inst.wires[wire_id].absolute_latency;
+        (latency != CALCULATE_LATENCY_LATER).then_some(latency)
+    }
+}
+
+/// Collects the wires to hint for, keyed by the byte position the hint should be placed at.
+/// Keying by position deduplicates `int x = ...`, where the declaration and the write target end at the same place
+fn gather_hinted_wires(md: &Module, byte_range: &Range<usize>, hinted: &mut BTreeMap<usize, HintedWire>) {
+    for (id, instr) in &md.instructions {
+        let (span, wire) = match instr {
+            Instruction::Declaration(decl) => {
+                if decl.identifier_type.is_generative() {
+                    continue;
+                }
+                (decl.name_span, HintedWire::Local(id))
+            }
+            Instruction::Write(write) => {
+                let wire = match &write.to.root {
+                    WireReferenceRoot::LocalDecl(decl_id, _) => {
+                        if md.instructions[*decl_id].unwrap_wire_declaration().identifier_type.is_generative() {
+                            continue;
+                        }
+                        HintedWire::Local(*decl_id)
+                    }
+                    WireReferenceRoot::SubModulePort(port) => HintedWire::SubModulePort(port.submodule_decl, port.port),
+                    WireReferenceRoot::NamedConstant(_, _) => continue,
+                };
+                (write.to_span, wire)
+            }
+            _ => continue,
+        };
+        let pos = span.into_range().end;
+        if byte_range.contains(&pos) || byte_range.end == pos {
+            hinted.entry(pos).or_insert(wire);
+        }
+    }
+}
+
+/// Shows the absolute latency of every declaration and write target as `'N`.
+/// If the instances of the module disagree, the range of latencies is shown as `'min..max`
+pub fn make_inlay_hints(file_id: FileUUID, byte_range: Range<usize>, linker: &Linker) -> Vec<InlayHint> {
+    let file_data = &linker.files[file_id];
+
+    let mut result = Vec::new();
+    for global in &file_data.associated_values {
+        let NameElem::Module(md_id) = *global else {
+            continue;
+        };
+        let md = &linker.modules[md_id];
+
+        let mut hinted = BTreeMap::new();
+        gather_hinted_wires(md, &byte_range, &mut hinted);
+
+        for (pos, wire) in hinted {
+            let mut latencies: Option<(i64, i64)> = None;
+            md.instantiations.for_each_instance(|_template_args, inst| {
+                if let Some(lat) = wire.latency_in(inst) {
+                    latencies = Some(match latencies {
+                        None => (lat, lat),
+                        Some((min, max)) => (min.min(lat), max.max(lat)),
+                    });
+                }
+            });
+            let Some((min, max)) = latencies else {
+                continue;
+            };
+            let label = if min == max {
+                format!("'{min}")
+            } else {
+                format!("'{min}..{max}")
+            };
+            result.push(InlayHint {
+                position: to_position(file_data.file_text.byte_to_utf16_linecol(pos)),
+                label: InlayHintLabel::String(label),
+                kind: Some(InlayHintKind::TYPE),
+                text_edits: None,
+                tooltip: None,
+                padding_left: None,
+                padding_right: None,
+                data: None,
+            });
+        }
+    }
+    result
+}
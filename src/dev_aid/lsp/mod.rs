@@ -1,26 +1,41 @@
+mod call_hierarchy;
+mod document_symbols;
+mod generated_code;
 mod hover_info;
+mod inlay_hints;
 mod semantic_tokens;
+mod signature_help;
 mod tree_walk;
 
 use crate::{compiler_top::LinkerExtraFileInfoManager, prelude::*};
 
+use call_hierarchy::{incoming_calls, outgoing_calls, prepare_call_hierarchy};
+use document_symbols::{make_document_symbols, make_folding_ranges, make_workspace_symbols};
+use generated_code::{generated_code, GENERATED_CODE_METHOD};
 use hover_info::hover;
+use inlay_hints::make_inlay_hints;
 use lsp_types::{notification::*, request::Request, *};
 use semantic_tokens::{make_semantic_tokens, semantic_token_capabilities};
+use signature_help::signature_help;
 use std::{collections::HashMap, error::Error, net::SocketAddr, path::PathBuf};
 
 use crate::{
-    config::config,
+    config::ConfigStruct,
+    dev_aid::formatter::{format_file, format_range, Indent},
     errors::{CompileError, ErrorLevel},
     file_position::{FileText, LineCol},
-    flattening::Instruction,
-    linker::FileData,
+    flattening::{Instruction, Module},
+    linker::{Documentation, FileData, NameElem},
+    logging::{log_enabled, LogLevel, LogSubsystem},
+    project::Project,
+    typing::template::TemplateInputKind,
 };
 
 use tree_walk::{get_selected_object, InModule, LocationInfo};
 
 use self::tree_walk::RefersTo;
 
+/// LSP positions count columns in UTF-16 code units. Convert with [FileText::utf16_linecol_to_byte_clamp] and [FileText::byte_to_utf16_linecol]
 fn from_position(pos: lsp_types::Position) -> LineCol {
     LineCol {
         line: pos.line as usize,
@@ -33,13 +48,27 @@ fn to_position(char_line: LineCol) -> lsp_types::Position {
         character: char_line.col as u32,
     }
 }
+/// Files opened by the editor are identified by their Url, but files added from disk, like the standard library, by their path
+fn file_uri(file_data: &FileData) -> Option<Url> {
+    Url::parse(&file_data.file_identifier)
+        .ok()
+        .or_else(|| Url::from_file_path(&file_data.file_identifier).ok())
+}
 fn span_to_lsp_range(file_text: &FileText, ch_sp: Span) -> lsp_types::Range {
-    let rng = file_text.get_span_linecol_range(ch_sp);
+    let rng = file_text.get_span_utf16_linecol_range(ch_sp);
     Range {
         start: to_position(rng.start),
         end: to_position(rng.end),
     }
 }
+/// Formatting uses the indentation the editor is configured with
+fn editor_indent(options: &FormattingOptions) -> Indent {
+    if options.insert_spaces {
+        Indent::Spaces(options.tab_size as usize)
+    } else {
+        Indent::Tabs
+    }
+}
 fn cvt_location_list(location_vec: Vec<SpanFile>, linker: &Linker) -> Vec<Location> {
     location_vec
         .into_iter()
@@ -78,10 +107,15 @@ impl Linker {
     fn find_uri(&self, uri: &Url) -> Option<FileUUID> {
         self.find_file(uri.as_str())
     }
-    fn update_text(&mut self, uri: &Url, new_file_text: String, manager : &mut LSPFileManager) {
-        self.add_or_update_file(uri.as_str(), new_file_text, manager);
+    fn update_text(&mut self, uri: &Url, content_changes: Vec<TextDocumentContentChangeEvent>, manager : &mut LSPFileManager) {
+        let file_id = self.ensure_contains_file(uri, manager);
 
-        self.recompile_all();
+        let edits = content_changes.into_iter().map(|change| {
+            (change.range.map(|range| from_position(range.start)..from_position(range.end)), change.text)
+        });
+        let changed_globals = self.update_file_incremental(file_id, edits, manager);
+
+        self.recompile_dependents_of(changed_globals);
     }
     fn ensure_contains_file(&mut self, uri: &Url, manager : &mut LSPFileManager) -> FileUUID {
         if let Some(found) = self.find_uri(uri) {
@@ -103,7 +137,7 @@ impl Linker {
 
         let position = file_data
             .file_text
-            .linecol_to_byte_clamp(from_position(text_pos.position));
+            .utf16_linecol_to_byte_clamp(from_position(text_pos.position));
 
         (file_id, position)
     }
@@ -151,8 +185,8 @@ fn convert_diagnostic(
     Diagnostic::new(
         error_pos,
         Some(severity),
-        None,
-        None,
+        Some(NumberOrString::String(err.code.code().to_owned())),
+        Some("sus".to_owned()),
         err.reason.clone(),
         Some(related_info),
         None,
@@ -199,45 +233,215 @@ impl LinkerExtraFileInfoManager for LSPFileManager {
     }
 }
 
-fn initialize_all_files(init_params: &InitializeParams) -> (Linker, LSPFileManager) {
-    let mut linker = Linker::new();
+fn initialize_all_files(init_params: &InitializeParams, config: ConfigStruct) -> (Linker, LSPFileManager) {
+    let mut linker = Linker::new(config);
     let mut manager = LSPFileManager{};
 
-    linker.add_standard_library(&mut manager);
+    linker.add_libraries(&mut manager);
 
     if let Some(workspace_folder) = &init_params.workspace_folders {
         for folder in workspace_folder {
             let Ok(path) = folder.uri.to_file_path() else {continue};
 
-            linker.add_all_files_in_directory(&path, &mut manager);
+            // Projects with a manifest list their own sources and dependencies
+            match Project::find_in(&path).and_then(|project| project.map(|p| p.source_files(&linker.config)).transpose()) {
+                Ok(Some(source_files)) => {
+                    for file_path in source_files {
+                        linker.add_file_from_disk_if_new(&file_path, &mut manager);
+                    }
+                }
+                Ok(None) => linker.add_all_files_in_directory(&path, &mut manager),
+                Err(err) => {
+                    log!(linker.config, Error, Lsp, "{err}");
+                    linker.add_all_files_in_directory(&path, &mut manager);
+                }
+            }
         }
     }
     linker.recompile_all();
     (linker, manager)
 }
 
+/// What the text right before the cursor asks to be completed
+enum CompletionContext<'t> {
+    /// `submodule.` completes the ports and interfaces of the submodule
+    Member(&'t str),
+    /// `Module::<` completes the names of its template arguments, for the `NAME = value` syntax
+    TemplateArgs(&'t str),
+    General,
+}
+
+fn ends_with_identifier(text: &str) -> &str {
+    let start = text
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map_or(0, |i| i + 1);
+    &text[start..]
+}
+
+fn find_completion_context(text_before: &str) -> CompletionContext {
+    // The partially typed word is what's being completed, so it's not part of the context
+    let before_word = text_before
+        .trim_end_matches(|c: char| c.is_alphanumeric() || c == '_')
+        .trim_end();
+
+    if let Some(before_dot) = before_word.strip_suffix('.') {
+        let submodule_name = ends_with_identifier(before_dot.trim_end());
+        if !submodule_name.is_empty() {
+            return CompletionContext::Member(submodule_name);
+        }
+    }
+
+    // Template argument names are only useful at the start of an argument
+    if before_word.ends_with(['<', ',']) {
+        let mut depth = 0;
+        for (i, c) in before_word.char_indices().rev() {
+            match c {
+                '>' | ')' | ']' => depth += 1,
+                '(' | '[' if depth == 0 => break,
+                '(' | '[' => depth -= 1,
+                '<' if depth == 0 => {
+                    if let Some(before_template) = before_word[..i].trim_end().strip_suffix("::") {
+                        let global_name = ends_with_identifier(before_template.trim_end());
+                        if !global_name.is_empty() {
+                            return CompletionContext::TemplateArgs(global_name);
+                        }
+                    }
+                    break;
+                }
+                '<' => depth -= 1,
+                ';' | '{' | '}' => break,
+                _ => {}
+            }
+        }
+    }
+
+    CompletionContext::General
+}
+
+fn documentation_detail(documentation: &Documentation, file_text: &FileText) -> Option<String> {
+    let doc = documentation.to_string(file_text);
+    let doc = doc.trim();
+    (!doc.is_empty()).then(|| doc.to_owned())
+}
+
+fn gather_member_completions(linker: &Linker, md: &Module, submodule_name: &str, result: &mut Vec<CompletionItem>) {
+    for (_id, instr) in &md.instructions {
+        let Instruction::SubModule(sm) = instr else {
+            continue;
+        };
+        if !sm.name.as_ref().map_or(false, |(name, _span)| name == submodule_name) {
+            continue;
+        }
+        let submod_md = &linker.modules[sm.module_ref.id];
+        let submod_file_text = &linker.files[submod_md.link_info.file].file_text;
+        for (_port_id, port) in &submod_md.ports {
+            let decl = submod_md.instructions[port.declaration_instruction].unwrap_wire_declaration();
+            result.push(CompletionItem {
+                label: port.name.clone(),
+                kind: Some(CompletionItemKind::FIELD),
+                detail: documentation_detail(&decl.documentation, submod_file_text),
+                ..Default::default()
+            });
+        }
+        for (_interface_id, interface) in &submod_md.interfaces {
+            result.push(CompletionItem {
+                label: interface.name.clone(),
+                kind: Some(CompletionItemKind::METHOD),
+                ..Default::default()
+            });
+        }
+        return;
+    }
+}
+
+fn gather_template_arg_completions(linker: &Linker, global_name: &str, result: &mut Vec<CompletionItem>) {
+    let found = linker
+        .modules
+        .iter()
+        .find(|(_id, md)| md.link_info.name == global_name)
+        .map(|(id, md)| (NameElem::Module(id), &md.link_info))
+        .or_else(|| {
+            linker
+                .types
+                .iter()
+                .find(|(_id, typ)| typ.link_info.name == global_name)
+                .map(|(id, typ)| (NameElem::Type(id), &typ.link_info))
+        });
+    let Some((global, link_info)) = found else {
+        return;
+    };
+    let file_text = &linker.files[link_info.file].file_text;
+    for (_id, input) in &link_info.template_arguments {
+        let detail = match (&input.kind, global) {
+            (TemplateInputKind::Generative(gen), NameElem::Module(md_id)) => {
+                let decl = linker.modules[md_id].instructions[gen.declaration_instruction].unwrap_wire_declaration();
+                documentation_detail(&decl.documentation, file_text)
+            }
+            _ => None,
+        };
+        result.push(CompletionItem {
+            label: input.name.clone(),
+            kind: Some(CompletionItemKind::TYPE_PARAMETER),
+            detail,
+            insert_text: Some(format!("{} = ", input.name)),
+            ..Default::default()
+        });
+    }
+}
+
 fn gather_completions(linker: &Linker, file_id: FileUUID, position: usize) -> Vec<CompletionItem> {
     let mut result = Vec::new();
 
+    let file_text = &linker.files[file_id].file_text;
+    let context = find_completion_context(&file_text.file_text[..position]);
+    let current_module = linker
+        .modules
+        .iter()
+        .find(|(_id, md)| md.link_info.file == file_id && md.link_info.span.contains_pos(position))
+        .map(|(_id, md)| md);
+
+    match context {
+        CompletionContext::Member(submodule_name) => {
+            if let Some(md) = current_module {
+                gather_member_completions(linker, md, submodule_name, &mut result);
+            }
+            return result;
+        }
+        CompletionContext::TemplateArgs(global_name) => {
+            gather_template_arg_completions(linker, global_name, &mut result);
+            return result;
+        }
+        CompletionContext::General => {}
+    }
+
     use crate::linker::Linkable;
     for (_, m) in &linker.modules {
         result.push(CompletionItem {
             label: m.link_info.name.to_string(),
             kind: Some(CompletionItemKind::FUNCTION),
+            detail: documentation_detail(&m.link_info.documentation, &linker.files[m.link_info.file].file_text),
             ..Default::default()
         });
-
-        if m.link_info.file == file_id && m.link_info.span.contains_pos(position) {
-            for (_id, v) in &m.instructions {
-                if let Instruction::Declaration(d) = v {
-                    result.push(CompletionItem {
-                        label: d.name.to_string(),
-                        kind: Some(CompletionItemKind::VARIABLE),
-                        ..Default::default()
-                    });
-                }
+    }
+    if let Some(md) = current_module {
+        for (_id, v) in &md.instructions {
+            if let Instruction::Declaration(d) = v {
+                result.push(CompletionItem {
+                    label: d.name.to_string(),
+                    kind: Some(CompletionItemKind::VARIABLE),
+                    detail: documentation_detail(&d.documentation, file_text),
+                    ..Default::default()
+                });
             }
         }
+        // The declared domain names, [Module::domains] can also contain inferred ones
+        for (_id, domain_name) in &md.domain_names {
+            result.push(CompletionItem {
+                label: domain_name.clone(),
+                kind: Some(CompletionItemKind::ENUM_MEMBER),
+                ..Default::default()
+            });
+        }
     }
     for (_, c) in &linker.constants {
         result.push(CompletionItem {
@@ -250,6 +454,7 @@ fn gather_completions(linker: &Linker, file_id: FileUUID, position: usize) -> Ve
         result.push(CompletionItem {
             label: t.get_name().to_string(),
             kind: Some(CompletionItemKind::STRUCT),
+            detail: documentation_detail(&t.link_info.documentation, &linker.files[t.link_info.file].file_text),
             ..Default::default()
         });
     }
@@ -344,7 +549,7 @@ fn handle_request(
         request::HoverRequest::METHOD => {
             let params: HoverParams =
                 serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
-            println!("HoverRequest");
+            log!(linker.config, Debug, Lsp, "HoverRequest");
 
             let (file_uuid, pos) =
                 linker.location_in_file(&params.text_document_position_params, manager);
@@ -354,7 +559,7 @@ fn handle_request(
             let range = if let Some((location, info)) =
                 get_selected_object(linker, file_uuid, pos)
             {
-                if config().lsp_debug_mode {
+                if linker.config.lsp_debug_mode {
                     hover_list.push(MarkedString::String(format!("{info:?}")))
                 } else {
                     hover_list = hover(info, linker, file_data);
@@ -371,7 +576,7 @@ fn handle_request(
         request::GotoDefinition::METHOD => {
             let params: GotoDefinitionParams =
                 serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
-            println!("GotoDefinition");
+            log!(linker.config, Debug, Lsp, "GotoDefinition");
 
             let (file_uuid, pos) =
                 linker.location_in_file(&params.text_document_position_params, manager);
@@ -418,7 +623,7 @@ fn handle_request(
         request::SemanticTokensFullRequest::METHOD => {
             let params: SemanticTokensParams =
                 serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
-            println!("SemanticTokensFullRequest");
+            log!(linker.config, Debug, Lsp, "SemanticTokensFullRequest");
 
             let uuid = linker.ensure_contains_file(&params.text_document.uri, manager);
 
@@ -430,7 +635,7 @@ fn handle_request(
         request::DocumentHighlightRequest::METHOD => {
             let params: DocumentHighlightParams =
                 serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
-            println!("DocumentHighlight");
+            log!(linker.config, Debug, Lsp, "DocumentHighlight");
 
             let (file_id, pos) = linker.location_in_file(&params.text_document_position_params, manager);
             let file_data = &linker.files[file_id];
@@ -449,7 +654,7 @@ fn handle_request(
         request::References::METHOD => {
             let params: ReferenceParams =
                 serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
-            println!("FindAllReferences");
+            log!(linker.config, Debug, Lsp, "FindAllReferences");
 
             let (file_id, pos) = linker.location_in_file(&params.text_document_position, manager);
 
@@ -461,7 +666,7 @@ fn handle_request(
         request::Rename::METHOD => {
             let params: RenameParams =
                 serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
-            println!("Rename");
+            log!(linker.config, Debug, Lsp, "Rename");
 
             let (file_id, pos) = linker.location_in_file(&params.text_document_position, manager);
 
@@ -485,7 +690,7 @@ fn handle_request(
                 })
                 .collect();
 
-            println!("{changes:?}");
+            log!(linker.config, Trace, Lsp, "{changes:?}");
 
             serde_json::to_value(WorkspaceEdit {
                 changes: Some(changes),
@@ -496,7 +701,7 @@ fn handle_request(
         request::Completion::METHOD => {
             let params: CompletionParams =
                 serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
-            println!("Completion");
+            log!(linker.config, Debug, Lsp, "Completion");
 
             let (file_uuid, position) = linker.location_in_file(&params.text_document_position, manager);
 
@@ -506,8 +711,162 @@ fn handle_request(
                 position,
             )))
         }
+        request::CodeActionRequest::METHOD => {
+            let params: CodeActionParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+            log!(linker.config, Debug, Lsp, "CodeAction");
+
+            let file_id = linker.ensure_contains_file(&params.text_document.uri, manager);
+            let file_data = &linker.files[file_id];
+            let requested_start = file_data.file_text.utf16_linecol_to_byte_clamp(from_position(params.range.start));
+            let requested_end = file_data.file_text.utf16_linecol_to_byte_clamp(from_position(params.range.end));
+
+            let mut actions: Vec<CodeActionOrCommand> = Vec::new();
+            linker.for_all_errors_in_file(file_id, |err| {
+                let err_range = err.position.into_range();
+                if err.suggestions.is_empty() || err_range.start > requested_end || requested_start > err_range.end {
+                    return;
+                }
+                let diagnostic = convert_diagnostic(err, &file_data.file_text, linker);
+                for suggestion in &err.suggestions {
+                    let text_edit = TextEdit {
+                        range: span_to_lsp_range(&file_data.file_text, suggestion.position),
+                        new_text: suggestion.replace_with.clone(),
+                    };
+                    actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                        title: suggestion.description(),
+                        kind: Some(CodeActionKind::QUICKFIX),
+                        diagnostics: Some(vec![diagnostic.clone()]),
+                        edit: Some(WorkspaceEdit {
+                            changes: Some(HashMap::from([(params.text_document.uri.clone(), vec![text_edit])])),
+                            document_changes: None,
+                            change_annotations: None,
+                        }),
+                        is_preferred: Some(err.suggestions.len() == 1),
+                        ..Default::default()
+                    }));
+                }
+            });
+
+            serde_json::to_value(actions)
+        }
+        request::InlayHintRequest::METHOD => {
+            let params: InlayHintParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+            log!(linker.config, Debug, Lsp, "InlayHint");
+
+            let file_id = linker.ensure_contains_file(&params.text_document.uri, manager);
+            let file_text = &linker.files[file_id].file_text;
+            let start = file_text.utf16_linecol_to_byte_clamp(from_position(params.range.start));
+            let end = file_text.utf16_linecol_to_byte_clamp(from_position(params.range.end));
+
+            serde_json::to_value(make_inlay_hints(file_id, start..end, linker))
+        }
+        request::SignatureHelpRequest::METHOD => {
+            let params: SignatureHelpParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+            log!(linker.config, Debug, Lsp, "SignatureHelp");
+
+            let (file_id, pos) = linker.location_in_file(&params.text_document_position_params, manager);
+
+            serde_json::to_value(signature_help(linker, file_id, pos))
+        }
+        request::DocumentSymbolRequest::METHOD => {
+            let params: DocumentSymbolParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+            log!(linker.config, Debug, Lsp, "DocumentSymbol");
+
+            let file_id = linker.ensure_contains_file(&params.text_document.uri, manager);
+
+            serde_json::to_value(DocumentSymbolResponse::Nested(make_document_symbols(linker, file_id)))
+        }
+        request::WorkspaceSymbolRequest::METHOD => {
+            let params: WorkspaceSymbolParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+            log!(linker.config, Debug, Lsp, "WorkspaceSymbol");
+
+            serde_json::to_value(WorkspaceSymbolResponse::Flat(make_workspace_symbols(linker, &params.query)))
+        }
+        request::FoldingRangeRequest::METHOD => {
+            let params: FoldingRangeParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+            log!(linker.config, Debug, Lsp, "FoldingRange");
+
+            let file_id = linker.ensure_contains_file(&params.text_document.uri, manager);
+
+            serde_json::to_value(make_folding_ranges(&linker.files[file_id]))
+        }
+        request::CallHierarchyPrepare::METHOD => {
+            let params: CallHierarchyPrepareParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+            log!(linker.config, Debug, Lsp, "CallHierarchyPrepare");
+
+            let (file_id, pos) = linker.location_in_file(&params.text_document_position_params, manager);
+
+            serde_json::to_value(prepare_call_hierarchy(linker, file_id, pos))
+        }
+        request::CallHierarchyIncomingCalls::METHOD => {
+            let params: CallHierarchyIncomingCallsParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+            log!(linker.config, Debug, Lsp, "CallHierarchyIncomingCalls");
+
+            serde_json::to_value(incoming_calls(linker, &params.item))
+        }
+        request::CallHierarchyOutgoingCalls::METHOD => {
+            let params: CallHierarchyOutgoingCallsParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+            log!(linker.config, Debug, Lsp, "CallHierarchyOutgoingCalls");
+
+            serde_json::to_value(outgoing_calls(linker, &params.item))
+        }
+        GENERATED_CODE_METHOD => {
+            log!(linker.config, Debug, Lsp, "GeneratedCode");
+            let uri: Url = serde_json::from_value(params["textDocument"]["uri"].clone())?;
+
+            let file_id = linker.ensure_contains_file(&uri, manager);
+
+            Ok(generated_code(linker, file_id, &params))
+        }
+        request::Formatting::METHOD => {
+            let params: DocumentFormattingParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+            log!(linker.config, Debug, Lsp, "Formatting");
+
+            let file_id = linker.ensure_contains_file(&params.text_document.uri, manager);
+            let file_data = &linker.files[file_id];
+
+            let edits: Vec<TextEdit> = format_file(&file_data.file_text.file_text, &file_data.tree, editor_indent(&params.options))
+                .into_iter()
+                .map(|formatted| TextEdit {
+                    range: span_to_lsp_range(&file_data.file_text, Span::from(0..file_data.file_text.len())),
+                    new_text: formatted,
+                })
+                .collect();
+            serde_json::to_value(edits)
+        }
+        request::RangeFormatting::METHOD => {
+            let params: DocumentRangeFormattingParams =
+                serde_json::from_value(params).expect("JSON Encoding Error while parsing params");
+            log!(linker.config, Debug, Lsp, "RangeFormatting");
+
+            let file_id = linker.ensure_contains_file(&params.text_document.uri, manager);
+            let file_data = &linker.files[file_id];
+            let start = file_data.file_text.utf16_linecol_to_byte_clamp(from_position(params.range.start));
+            let end = file_data.file_text.utf16_linecol_to_byte_clamp(from_position(params.range.end));
+
+            let indent = editor_indent(&params.options);
+            let edits: Vec<TextEdit> = format_range(&file_data.file_text.file_text, &file_data.tree, start..end, indent)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(range, new_text)| TextEdit {
+                    range: span_to_lsp_range(&file_data.file_text, Span::from(range)),
+                    new_text,
+                })
+                .collect();
+            serde_json::to_value(edits)
+        }
         req => {
-            println!("Other request: {req:?}");
+            log!(linker.config, Debug, Lsp, "Other request: {req:?}");
             Ok(serde_json::Value::Null)
         }
     }
@@ -522,26 +881,22 @@ fn handle_notification(
 ) -> Result<(), Box<dyn Error + Sync + Send>> {
     match notification.method.as_str() {
         notification::DidChangeTextDocument::METHOD => {
-            println!("DidChangeTextDocument");
+            log!(linker.config, Debug, Lsp, "DidChangeTextDocument");
             let params: DidChangeTextDocumentParams = serde_json::from_value(notification.params)
                 .expect("JSON Encoding Error while parsing params");
 
-            let mut content_change_iter = params.content_changes.into_iter();
-            let only_change = content_change_iter.next().unwrap();
-            assert!(content_change_iter.next().is_none());
-            assert!(only_change.range.is_none());
-            linker.update_text(&params.text_document.uri, only_change.text, manager);
+            linker.update_text(&params.text_document.uri, params.content_changes, manager);
 
             push_all_errors(connection, &linker)?;
         }
         notification::DidChangeWatchedFiles::METHOD => {
-            println!("Workspace Files modified");
-            (*linker, *manager) = initialize_all_files(initialize_params);
+            log!(linker.config, Debug, Lsp, "Workspace Files modified");
+            (*linker, *manager) = initialize_all_files(initialize_params, linker.config.clone());
 
             push_all_errors(&connection, &linker)?;
         }
         other => {
-            println!("got notification: {other:?}");
+            log!(linker.config, Debug, Lsp, "got notification: {other:?}");
         }
     }
     Ok(())
@@ -550,22 +905,22 @@ fn handle_notification(
 fn main_loop(
     connection: lsp_server::Connection,
     initialize_params: serde_json::Value,
+    config: ConfigStruct,
 ) -> Result<(), Box<dyn Error + Sync + Send>> {
-    println!("initialize_params: ");
-    println!("{initialize_params}");
+    log!(config, Trace, Lsp, "initialize_params: {initialize_params}");
 
     let initialize_params: InitializeParams = serde_json::from_value(initialize_params).unwrap();
 
-    let (mut linker, mut manager) = initialize_all_files(&initialize_params);
+    let (mut linker, mut manager) = initialize_all_files(&initialize_params, config);
 
     push_all_errors(&connection, &linker)?;
 
-    println!("starting LSP main loop");
+    log!(linker.config, Info, Lsp, "starting LSP main loop");
     for msg in &connection.receiver {
         match msg {
             lsp_server::Message::Request(req) => {
                 if connection.handle_shutdown(&req)? {
-                    println!("Shutdown request");
+                    log!(linker.config, Debug, Lsp, "Shutdown request");
                     return Ok(());
                 }
 
@@ -582,7 +937,7 @@ fn main_loop(
                     .send(lsp_server::Message::Response(response))?;
             }
             lsp_server::Message::Response(resp) => {
-                println!("got response: {resp:?}");
+                log!(linker.config, Debug, Lsp, "got response: {resp:?}");
             }
             lsp_server::Message::Notification(notification) => {
                 handle_notification(
@@ -595,26 +950,31 @@ fn main_loop(
             }
         }
 
-        println!("All loaded files:");
-        for (_id, file) in &linker.files {
-            println!("File: {}", &file.file_identifier);
+        if log_enabled(&linker.config, LogLevel::Trace, LogSubsystem::Lsp) {
+            eprintln!("All loaded files:");
+            for (_id, file) in &linker.files {
+                eprintln!("File: {}", &file.file_identifier);
+            }
         }
     }
     Ok(())
 }
 
-pub fn lsp_main() -> Result<(), Box<dyn Error + Sync + Send>> {
+pub fn lsp_main(config: ConfigStruct) -> Result<(), Box<dyn Error + Sync + Send>> {
     std::env::set_var("RUST_BACKTRACE", "1"); // Enable backtrace because I can't set it in Env vars
 
-    println!("starting LSP server");
+    log!(config, Info, Lsp, "starting LSP server");
 
-    // Create the transport. Includes the stdio (stdin and stdout) versions but this could
-    // also be implemented to use sockets or HTTP.
+    // Create the transport. stdout is reserved for the protocol when using stdio, so all logging goes to stderr
     //let (connection, io_threads) = Connection::listen(SocketAddr::from(([127,0,0,1], 25000)))?;
-    println!("Connecting on port {}...", config().lsp_port);
-    let (connection, io_threads) =
-        lsp_server::Connection::connect(SocketAddr::from(([127, 0, 0, 1], config().lsp_port)))?;
-    println!("connection established");
+    let (connection, io_threads) = if config.lsp_stdio {
+        log!(config, Info, Lsp, "Communicating over stdio");
+        lsp_server::Connection::stdio()
+    } else {
+        log!(config, Info, Lsp, "Connecting on port {}...", config.lsp_port);
+        lsp_server::Connection::connect(SocketAddr::from(([127, 0, 0, 1], config.lsp_port)))?
+    };
+    log!(config, Info, Lsp, "connection established");
 
     // Run the server and wait for the two threads to end (typically by trigger LSP Exit event).
     let server_capabilities = serde_json::to_value(&ServerCapabilities {
@@ -623,20 +983,35 @@ pub fn lsp_main() -> Result<(), Box<dyn Error + Sync + Send>> {
         references_provider: Some(OneOf::Left(true)),
         hover_provider: Some(HoverProviderCapability::Simple(true)),
         rename_provider: Some(OneOf::Left(true)),
+        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+        inlay_hint_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        workspace_symbol_provider: Some(OneOf::Left(true)),
+        folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+        call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_range_formatting_provider: Some(OneOf::Left(true)),
+        signature_help_provider: Some(SignatureHelpOptions {
+            trigger_characters: Some(vec!["(".to_owned(), "<".to_owned(), ",".to_owned()]),
+            retrigger_characters: None,
+            work_done_progress_options: WorkDoneProgressOptions {
+                work_done_progress: None,
+            },
+        }),
         semantic_tokens_provider: Some(semantic_token_capabilities()),
         completion_provider: Some(CompletionOptions {
             resolve_provider: Some(true),
             ..Default::default()
         }),
-        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::INCREMENTAL)),
         ..Default::default()
     })
     .unwrap();
     let initialization_params = connection.initialize(server_capabilities)?;
-    main_loop(connection, initialization_params)?;
+    main_loop(connection, initialization_params, config.clone())?;
     io_threads.join()?;
 
     // Shut down gracefully.
-    println!("shutting down server");
+    log!(config, Info, Lsp, "shutting down server");
     Ok(())
 }
@@ -88,7 +88,7 @@ fn convert_to_semantic_tokens(
             let typ = get_semantic_token_type_from_ide_token(*ide_kind);
             let mod_bits = get_modifiers_for_token(*ide_kind);
 
-            let tok_range = file_data.file_text.get_span_linecol_range(*span);
+            let tok_range = file_data.file_text.get_span_utf16_linecol_range(*span);
             let start_pos = to_position(tok_range.start);
             let end_pos = to_position(tok_range.end);
 
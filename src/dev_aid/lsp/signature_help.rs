@@ -0,0 +1,246 @@
+use std::ops::Deref;
+
+use crate::prelude::*;
+
+use lsp_types::{
+    Documentation as LspDocumentation, ParameterInformation, ParameterLabel, SignatureHelp,
+    SignatureInformation,
+};
+
+use crate::file_position::{BracketSpan, FileText};
+use crate::flattening::{Declaration, FuncCallInstruction, Instruction, Module, WrittenType};
+use crate::linker::{FileData, LinkInfo, NameElem};
+use crate::typing::template::{GlobalReference, TemplateArgKind, TemplateArgs, TemplateInputKind};
+
+/// The call or template argument list the cursor is in. Nested lists are found later, so the last candidate wins
+enum SignatureTarget<'l> {
+    FuncCall(&'l Module, &'l FuncCallInstruction),
+    Template(NameElem, &'l TemplateArgs, BracketSpan),
+}
+
+impl<'l> SignatureTarget<'l> {
+    fn bracket_span(&self) -> BracketSpan {
+        match self {
+            SignatureTarget::FuncCall(_, fc) => fc.arguments_span,
+            SignatureTarget::Template(_, _, span) => *span,
+        }
+    }
+}
+
+fn gather_global_reference<'l, ID: Copy>(
+    global: &'l GlobalReference<ID>,
+    position: usize,
+    found: &mut Option<SignatureTarget<'l>>,
+) where
+    NameElem: From<ID>,
+{
+    if let Some(template_span) = global.template_span {
+        if template_span.inner_span().contains_pos(position) {
+            *found = Some(SignatureTarget::Template(
+                NameElem::from(global.id),
+                &global.template_args,
+                template_span,
+            ));
+        }
+    }
+    for (_id, arg) in global.template_args.iter_valids() {
+        if let TemplateArgKind::Type(typ) = &arg.kind {
+            gather_type(typ, position, found);
+        }
+    }
+}
+
+fn gather_type<'l>(typ: &'l WrittenType, position: usize, found: &mut Option<SignatureTarget<'l>>) {
+    match typ {
+        WrittenType::Error(_) | WrittenType::Template(_, _) => {}
+        WrittenType::Named(named_type) => gather_global_reference(named_type, position, found),
+        WrittenType::Array(_, arr_box) => {
+            let (content_typ, _size_id, _br_span) = arr_box.deref();
+            gather_type(content_typ, position, found);
+        }
+    }
+}
+
+fn find_signature_target<'l>(linker: &'l Linker, file_data: &'l FileData, position: usize) -> Option<SignatureTarget<'l>> {
+    let mut found = None;
+    for global in &file_data.associated_values {
+        let NameElem::Module(md_id) = *global else {
+            continue;
+        };
+        let md = &linker.modules[md_id];
+        if !md.link_info.span.contains_pos(position) {
+            continue;
+        }
+        for (_id, instr) in &md.instructions {
+            match instr {
+                Instruction::SubModule(sm) => gather_global_reference(&sm.module_ref, position, &mut found),
+                Instruction::Declaration(decl) => gather_type(&decl.typ_expr, position, &mut found),
+                Instruction::FuncCall(fc) => {
+                    if fc.arguments_span.inner_span().contains_pos(position) {
+                        found = Some(SignatureTarget::FuncCall(md, fc));
+                    }
+                }
+                Instruction::Wire(_) | Instruction::Write(_) | Instruction::IfStatement(_) | Instruction::ForStatement(_) => {}
+            }
+        }
+    }
+    found
+}
+
+/// Counts the commas between the opening bracket and the cursor that aren't nested in another bracket pair
+fn count_arguments_before(file_text: &FileText, bracket_span: BracketSpan, position: usize) -> usize {
+    let text = &file_text.file_text[bracket_span.inner_span().into_range().start..position];
+    let mut depth = 0;
+    let mut count = 0;
+    for c in text.chars() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => count += 1,
+            _ => {}
+        }
+    }
+    count
+}
+
+/// Builds up the label of a signature, and records the offsets of each parameter in it
+struct SignatureBuilder {
+    label: String,
+    parameters: Vec<ParameterInformation>,
+}
+
+impl SignatureBuilder {
+    fn parameter(&mut self, text: &str, documentation: String) {
+        let start = self.label.encode_utf16().count() as u32;
+        self.label.push_str(text);
+        let end = self.label.encode_utf16().count() as u32;
+        self.parameters.push(ParameterInformation {
+            label: ParameterLabel::LabelOffsets([start, end]),
+            documentation: (!documentation.is_empty()).then(|| LspDocumentation::String(documentation)),
+        });
+    }
+    fn finish(self, documentation: String, active_parameter: usize) -> SignatureHelp {
+        SignatureHelp {
+            signatures: vec![SignatureInformation {
+                label: self.label,
+                documentation: (!documentation.is_empty()).then(|| LspDocumentation::String(documentation)),
+                parameters: Some(self.parameters),
+                active_parameter: Some(active_parameter as u32),
+            }],
+            active_signature: Some(0),
+            active_parameter: Some(active_parameter as u32),
+        }
+    }
+}
+
+/// `type name'latency`, where the latency specifier is only present if it was written in the port declaration
+fn port_declaration_string(linker: &Linker, md: &Module, decl: &Declaration) -> String {
+    declaration_string(linker, &md.link_info, &md.instructions, decl)
+}
+
+/// `instructions` are those of the module or struct that `link_info` belongs to
+fn declaration_string(
+    linker: &Linker,
+    link_info: &LinkInfo,
+    instructions: &FlatAlloc<Instruction, FlatIDMarker>,
+    decl: &Declaration,
+) -> String {
+    let typ_str = decl.typ.typ.to_string(&linker.types, &link_info.template_arguments);
+    let mut result = format!("{typ_str} {}", decl.name);
+    if let Some(lat_spec) = decl.latency_specifier {
+        let lat_file_text = &linker.files[link_info.file].file_text;
+        result.push('\'');
+        result.push_str(&lat_file_text[instructions[lat_spec].unwrap_wire().span]);
+    }
+    result
+}
+
+fn func_call_signature(linker: &Linker, md: &Module, fc: &FuncCallInstruction, active_parameter: usize) -> SignatureHelp {
+    let submodule = md.instructions[fc.interface_reference.submodule_decl].unwrap_submodule();
+    let submod_md = &linker.modules[submodule.module_ref.id];
+    let submod_file_text = &linker.files[submod_md.link_info.file].file_text;
+    let interface = &submod_md.interfaces[fc.interface_reference.submodule_interface];
+
+    let mut builder = SignatureBuilder {
+        label: format!("{}(", interface.name),
+        parameters: Vec::new(),
+    };
+    for (i, port_id) in interface.func_call_inputs.into_iter().enumerate() {
+        if i != 0 {
+            builder.label.push_str(", ");
+        }
+        let decl = submod_md.instructions[submod_md.ports[port_id].declaration_instruction].unwrap_wire_declaration();
+        builder.parameter(
+            &port_declaration_string(linker, submod_md, decl),
+            decl.documentation.to_string(submod_file_text),
+        );
+    }
+    builder.label.push(')');
+    let outputs: Vec<String> = interface
+        .func_call_outputs
+        .into_iter()
+        .map(|port_id| {
+            let decl = submod_md.instructions[submod_md.ports[port_id].declaration_instruction].unwrap_wire_declaration();
+            port_declaration_string(linker, submod_md, decl)
+        })
+        .collect();
+    if !outputs.is_empty() {
+        builder.label.push_str(" -> ");
+        builder.label.push_str(&outputs.join(", "));
+    }
+
+    builder.finish(submod_md.link_info.documentation.to_string(submod_file_text), active_parameter)
+}
+
+fn template_signature(linker: &Linker, target: NameElem, active_parameter: usize) -> Option<SignatureHelp> {
+    let link_info = linker.get_link_info(target)?;
+    let file_text = &linker.files[link_info.file].file_text;
+
+    let mut builder = SignatureBuilder {
+        label: format!("{}::<", link_info.get_full_name()),
+        parameters: Vec::new(),
+    };
+    for (i, (_id, input)) in link_info.template_arguments.iter().enumerate() {
+        if i != 0 {
+            builder.label.push_str(", ");
+        }
+        match &input.kind {
+            TemplateInputKind::Type(_) => builder.parameter(&format!("type {}", input.name), String::new()),
+            TemplateInputKind::Generative(gen) => {
+                // Both modules and structs can have generative template inputs
+                let instructions = match target {
+                    NameElem::Module(md_id) => &linker.modules[md_id].instructions,
+                    NameElem::Type(typ_id) => &linker.types[typ_id].instructions,
+                    NameElem::Constant(_) => return None,
+                };
+                let decl = instructions[gen.declaration_instruction].unwrap_wire_declaration();
+                builder.parameter(
+                    &declaration_string(linker, link_info, instructions, decl),
+                    decl.documentation.to_string(file_text),
+                );
+            }
+        }
+    }
+    builder.label.push('>');
+
+    Some(builder.finish(link_info.documentation.to_string(file_text), active_parameter))
+}
+
+/// Shows the ports of the interface that is being called, or the template inputs of the global whose template arguments are being written
+pub fn signature_help(linker: &Linker, file_id: FileUUID, position: usize) -> Option<SignatureHelp> {
+    let file_data = &linker.files[file_id];
+    let target = find_signature_target(linker, file_data, position)?;
+    let comma_count = count_arguments_before(&file_data.file_text, target.bracket_span(), position);
+
+    match target {
+        SignatureTarget::FuncCall(md, fc) => Some(func_call_signature(linker, md, fc, comma_count)),
+        SignatureTarget::Template(target, template_args, _) => {
+            // Named template arguments can be in any order, so prefer the name over the position
+            let active_parameter = template_args
+                .iter_valids()
+                .find(|(_id, arg)| arg.name_specification.is_some() && arg.whole_span.contains_pos(position))
+                .map_or(comma_count, |(id, _arg)| id.get_hidden_value());
+            template_signature(linker, target, active_parameter)
+        }
+    }
+}
@@ -60,6 +60,7 @@ impl<'linker> From<LocationInfo<'linker>> for RefersTo {
                     match decl.is_port {
                         DeclarationPortInfo::NotPort => {}
                         DeclarationPortInfo::StructField { field_id:_ } => {}
+                        DeclarationPortInfo::Crossed { from:_, group_leader:_ } => {}
                         DeclarationPortInfo::RegularPort {
                             is_input: _,
                             port_id,
@@ -405,7 +406,10 @@ impl<'linker, Visitor: FnMut(Span, LocationInfo<'linker>), Pruner: Fn(Span) -> b
                         }
                     }
                     Instruction::Declaration(decl) => {
-                        self.walk_type(NameElem::Module(md_id), &md.link_info, &decl.typ_expr);
+                        // Crossed declarations copy the type of the original, it's already been walked there
+                        if !matches!(decl.is_port, DeclarationPortInfo::Crossed { .. }) {
+                            self.walk_type(NameElem::Module(md_id), &md.link_info, &decl.typ_expr);
+                        }
                         if decl.declaration_itself_is_not_written_to {
                             self.visit(
                                 decl.name_span,
@@ -440,7 +444,7 @@ impl<'linker, Visitor: FnMut(Span, LocationInfo<'linker>), Pruner: Fn(Span) -> b
         if !(self.should_prune)(typ.link_info.span) {
             self.walk_name_and_template_arguments(NameElem::Type(typ_id), &typ.link_info);
 
-            println!("TODO struct instructions")
+            eprintln!("TODO struct instructions")
         }
     }
 
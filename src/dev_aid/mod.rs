@@ -1,4 +1,8 @@
 pub mod ariadne_interface;
+pub mod formatter;
+pub mod json_diagnostics;
+pub mod sarif;
+pub mod watch;
 
 #[cfg(feature = "lsp")]
 pub mod lsp;
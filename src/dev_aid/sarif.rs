@@ -0,0 +1,183 @@
+use crate::prelude::*;
+
+use serde_json::{json, Value as JsonValue};
+
+use crate::errors::{CompileError, DiagnosticCode, DiagnosticCounts, ErrorLevel};
+use crate::file_position::FileText;
+
+fn sarif_level(level: &ErrorLevel) -> &'static str {
+    match level {
+        ErrorLevel::Error => "error",
+        ErrorLevel::Warning => "warning",
+    }
+}
+
+fn default_level(code: DiagnosticCode) -> &'static str {
+    if code.is_warning() {
+        "warning"
+    } else {
+        "error"
+    }
+}
+
+/// SARIF uses URIs, relative ones are resolved against the directory the compiler was run in
+fn artifact_location(file_identifier: &str) -> JsonValue {
+    json!({ "uri": file_identifier.replace('\\', "/") })
+}
+
+/// Lines and columns are 1-based. Columns count characters, as declared by the run's `columnKind`
+fn region(file_text: &FileText, span: Span) -> JsonValue {
+    let range = file_text.get_span_linecol_range(span);
+    let byte_range = span.into_range();
+    json!({
+        "startLine": range.start.line + 1,
+        "startColumn": range.start.col + 1,
+        "endLine": range.end.line + 1,
+        "endColumn": range.end.col + 1,
+        "byteOffset": byte_range.start,
+        "byteLength": byte_range.len(),
+    })
+}
+
+fn physical_location(linker: &Linker, file: FileUUID, span: Span) -> JsonValue {
+    let file_data = &linker.files[file];
+    json!({
+        "artifactLocation": artifact_location(&file_data.file_identifier),
+        "region": region(&file_data.file_text, span),
+    })
+}
+
+fn make_result(linker: &Linker, file: FileUUID, error: &CompileError, instance_name: Option<&str>) -> JsonValue {
+    let file_data = &linker.files[file];
+    let related_locations: Vec<JsonValue> = error
+        .infos
+        .iter()
+        .enumerate()
+        .map(|(idx, info)| {
+            json!({
+                "id": idx,
+                "message": { "text": info.info },
+                "physicalLocation": physical_location(linker, info.file, info.position),
+            })
+        })
+        .collect();
+    let fixes: Vec<JsonValue> = error
+        .suggestions
+        .iter()
+        .map(|suggestion| {
+            json!({
+                "description": { "text": suggestion.description() },
+                "artifactChanges": [{
+                    "artifactLocation": artifact_location(&file_data.file_identifier),
+                    "replacements": [{
+                        "deletedRegion": region(&file_data.file_text, suggestion.position),
+                        "insertedContent": { "text": suggestion.replace_with },
+                    }],
+                }],
+            })
+        })
+        .collect();
+
+    let rule_index = DiagnosticCode::ALL.iter().position(|c| *c == error.code).unwrap();
+    let mut result = json!({
+        "ruleId": error.code.code(),
+        "ruleIndex": rule_index,
+        "level": sarif_level(&error.level),
+        "message": { "text": error.reason },
+        "locations": [{ "physicalLocation": physical_location(linker, file, error.position) }],
+        "relatedLocations": related_locations,
+        "fixes": fixes,
+    });
+    if let Some(instance_name) = instance_name {
+        result["properties"] = json!({ "instance": instance_name });
+    }
+    result
+}
+
+/// A SARIF 2.1.0 log of all errors and warnings, for code scanning integrations.
+/// Every [DiagnosticCode] is a rule
+pub fn make_sarif_log(linker: &Linker) -> (JsonValue, DiagnosticCounts) {
+    let mut counts = DiagnosticCounts::default();
+    let mut results = Vec::new();
+    for (file_uuid, _f) in &linker.files {
+        linker.for_all_errors_in_file_with_instance(file_uuid, |err, instance_name| {
+            counts.add(err);
+            results.push(make_result(linker, file_uuid, err, instance_name));
+        });
+    }
+
+    let rules: Vec<JsonValue> = DiagnosticCode::ALL
+        .iter()
+        .map(|code| {
+            json!({
+                "id": code.code(),
+                "name": code.name(),
+                "shortDescription": { "text": code.summary() },
+                "fullDescription": { "text": code.explanation() },
+                "defaultConfiguration": { "level": default_level(*code) },
+            })
+        })
+        .collect();
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "sus_compiler",
+                    "version": std::env!("CARGO_PKG_VERSION"),
+                    "informationUri": std::env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules,
+                },
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }],
+    });
+    (log, counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler_top::test_util::compile;
+
+    #[test]
+    fn unknown_name_as_sarif() {
+        let (linker, _file_id) = compile("module M {\n    int a = b\n}\n");
+        let (log, counts) = make_sarif_log(&linker);
+        assert!(counts.errors >= 1);
+
+        let run = &log["runs"][0];
+        assert_eq!(log["version"], "2.1.0");
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), DiagnosticCode::ALL.len());
+        assert_eq!(run["tool"]["driver"]["rules"][2]["id"], "E0003");
+
+        let unknown_names: Vec<&JsonValue> = run["results"].as_array().unwrap().iter().filter(|result| result["ruleId"] == "E0003").collect();
+        assert_eq!(
+            unknown_names,
+            vec![&json!({
+                "ruleId": "E0003",
+                "ruleIndex": 2,
+                "level": "error",
+                "message": { "text": "No Global of the name 'b' was found. Did you forget to import it?" },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": "test.sus" },
+                        "region": {
+                            "startLine": 2,
+                            "startColumn": 13,
+                            "endLine": 2,
+                            "endColumn": 14,
+                            "byteOffset": 23,
+                            "byteLength": 1,
+                        },
+                    },
+                }],
+                "relatedLocations": [],
+                "fixes": [],
+            })]
+        );
+    }
+}
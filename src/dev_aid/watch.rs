@@ -0,0 +1,182 @@
+//! `--watch`: keeps the [Linker] alive and recompiles whenever a source file or a library changes on disk.
+//!
+//! Polls modification times, so it works the same on every platform and filesystem
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use crate::prelude::*;
+
+use crate::compiler_top::{library_directories, sus_files_in_directory, LinkerExtraFileInfoManager};
+use crate::linker::NameElem;
+
+use super::ariadne_interface::FileSourcesManager;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The watched files, by the path they were added to the [Linker] with, and their last modification time
+type WatchedFiles = HashMap<PathBuf, SystemTime>;
+
+fn modification_time(path: &PathBuf) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Libraries are added with canonical paths, see [Linker::add_file_from_disk]
+fn library_files(linker: &Linker) -> Vec<PathBuf> {
+    library_directories(&linker.config)
+        .iter()
+        .filter_map(|directory| sus_files_in_directory(&linker.config, directory).ok())
+        .flatten()
+        .filter_map(|path| path.canonicalize().ok())
+        .collect()
+}
+
+fn scan(linker: &Linker, initial_files: &[PathBuf]) -> WatchedFiles {
+    let source_files = linker.config.source_discovery.find_files(&linker.config, initial_files).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        Vec::new()
+    });
+    source_files
+        .into_iter()
+        .chain(library_files(linker))
+        .filter_map(|path| modification_time(&path).map(|time| (path, time)))
+        .collect()
+}
+
+/// Applies the differences between `old` and `new` to the linker, like the LSP does for edits.
+/// Returns the globals that were added, changed or removed, or None if no file changed
+fn apply_changes(
+    linker: &mut Linker,
+    manager: &mut FileSourcesManager,
+    old: &WatchedFiles,
+    new: &WatchedFiles,
+) -> Option<HashSet<NameElem>> {
+    let mut any_changed = false;
+    let mut changed_globals = HashSet::new();
+    for (path, time) in new {
+        if old.get(path) == Some(time) {
+            continue;
+        }
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(reason) => {
+                log!(linker.config, Error, Linker, "Could not read {}: {reason}", path.display());
+                continue;
+            }
+        };
+        log!(linker.config, Info, Linker, "Changed: {}", path.display());
+        let file_identifier = path.to_string_lossy();
+        match linker.find_file(&file_identifier) {
+            Some(file_id) => changed_globals.extend(linker.update_file_incremental(file_id, [(None, text)], manager)),
+            None => {
+                let file_id = linker.add_file(file_identifier.into_owned(), text, manager);
+                changed_globals.extend(linker.files[file_id].associated_values.iter().copied());
+            }
+        }
+        any_changed = true;
+    }
+    for path in old.keys().filter(|path| !new.contains_key(*path)) {
+        if let Some(file_id) = linker.find_file(&path.to_string_lossy()) {
+            log!(linker.config, Info, Linker, "Removed: {}", path.display());
+            changed_globals.extend(linker.files[file_id].associated_values.iter().copied());
+            manager.before_file_remove(file_id, linker);
+            linker.remove_file(file_id);
+            any_changed = true;
+        }
+    }
+    any_changed.then_some(changed_globals)
+}
+
+/// Never returns. `on_recompiled` is called once at the start, and after every recompilation
+pub fn watch(
+    mut linker: Linker,
+    mut manager: FileSourcesManager,
+    initial_files: &[PathBuf],
+    mut on_recompiled: impl FnMut(&Linker, &mut FileSourcesManager),
+) -> ! {
+    let mut watched = scan(&linker, initial_files);
+    on_recompiled(&linker, &mut manager);
+    log!(linker.config, Info, Linker, "Watching {} files for changes", watched.len());
+
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+
+        let new_watched = scan(&linker, initial_files);
+        if let Some(changed_globals) = apply_changes(&mut linker, &mut manager, &watched, &new_watched) {
+            linker.recompile_dependents_of(changed_globals);
+            on_recompiled(&linker, &mut manager);
+        }
+        watched = new_watched;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc::ArenaVector;
+    use crate::compiler_top::test_util::error_messages;
+    use crate::config::ConfigStruct;
+
+    /// Sets distinct modification times, so changes are seen even within the file system's timestamp resolution
+    fn write_at(path: &PathBuf, content: &str, seconds: u64) {
+        std::fs::write(path, content).unwrap();
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+        std::fs::File::options().write(true).open(path).unwrap().set_modified(time).unwrap();
+    }
+
+    fn watched(paths: &[&PathBuf]) -> WatchedFiles {
+        paths.iter().map(|path| ((*path).clone(), modification_time(path).unwrap())).collect()
+    }
+
+    /// Like one iteration of [watch]
+    fn poll(linker: &mut Linker, manager: &mut FileSourcesManager, old: &WatchedFiles, new: &WatchedFiles) -> bool {
+        let changed_globals = apply_changes(linker, manager, old, new);
+        let any_changed = changed_globals.is_some();
+        if let Some(changed_globals) = changed_globals {
+            linker.recompile_dependents_of(changed_globals);
+        }
+        any_changed
+    }
+
+    #[test]
+    fn changed_and_removed_files_recompile_their_dependents() {
+        let directory = std::env::temp_dir().join(format!("sus_watch_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        let a = directory.join("a.sus");
+        let b = directory.join("b.sus");
+        write_at(&a, "module A {}\n", 1);
+        write_at(&b, "module B {\n    A a\n}\n", 1);
+
+        let mut linker = Linker::new(ConfigStruct::default());
+        let mut manager = FileSourcesManager { file_sources: ArenaVector::new() };
+        let both = watched(&[&a, &b]);
+        assert!(poll(&mut linker, &mut manager, &WatchedFiles::new(), &both));
+        let b_id = linker.find_file(&b.to_string_lossy()).unwrap();
+        assert_eq!(error_messages(&linker, b_id), Vec::<String>::new());
+
+        // Nothing changed on disk
+        assert!(!poll(&mut linker, &mut manager, &both, &watched(&[&a, &b])));
+
+        // B is recompiled, even though its own file didn't change
+        write_at(&a, "module C {}\n", 2);
+        let renamed = watched(&[&a, &b]);
+        assert!(poll(&mut linker, &mut manager, &both, &renamed));
+        let unknown_a = vec!["No Global of the name 'A' was found. Did you forget to import it?".to_owned()];
+        assert_eq!(error_messages(&linker, b_id), unknown_a);
+
+        write_at(&a, "module A {}\n", 3);
+        let restored = watched(&[&a, &b]);
+        assert!(poll(&mut linker, &mut manager, &renamed, &restored));
+        assert_eq!(error_messages(&linker, b_id), Vec::<String>::new());
+
+        std::fs::remove_file(&a).unwrap();
+        let without_a = watched(&[&b]);
+        assert!(poll(&mut linker, &mut manager, &restored, &without_a));
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert!(linker.find_file(&a.to_string_lossy()).is_none());
+        assert_eq!(error_messages(&linker, b_id), unknown_a);
+    }
+}
@@ -13,6 +13,275 @@ pub enum ErrorLevel {
     Warning,
 }
 
+/// Stable identifier for each kind of diagnostic, so tools and users can refer to them.
+/// Codes must never be renumbered, only added
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticCode {
+    SyntaxError,
+    NameCollision,
+    UnknownName,
+    WrongKindOfName,
+    TypeError,
+    DomainMismatch,
+    GenerativeRequired,
+    TemplateArguments,
+    ArgumentCount,
+    InvalidDeclaration,
+    InvalidWrite,
+    InvalidWireReference,
+    InvalidFunctionCall,
+    InvalidCross,
+    Latency,
+    Instantiation,
+    GenerativeExecution,
+    NotImplemented,
+
+    UnusedVariable,
+    UnusedPort,
+    UnusedResult,
+    StatementsOnSameLine,
+    RedundantCross,
+    UnknownLint,
+}
+
+impl DiagnosticCode {
+    pub const ALL: [DiagnosticCode; 24] = [
+        Self::SyntaxError,
+        Self::NameCollision,
+        Self::UnknownName,
+        Self::WrongKindOfName,
+        Self::TypeError,
+        Self::DomainMismatch,
+        Self::GenerativeRequired,
+        Self::TemplateArguments,
+        Self::ArgumentCount,
+        Self::InvalidDeclaration,
+        Self::InvalidWrite,
+        Self::InvalidWireReference,
+        Self::InvalidFunctionCall,
+        Self::InvalidCross,
+        Self::Latency,
+        Self::Instantiation,
+        Self::GenerativeExecution,
+        Self::NotImplemented,
+        Self::UnusedVariable,
+        Self::UnusedPort,
+        Self::UnusedResult,
+        Self::StatementsOnSameLine,
+        Self::RedundantCross,
+        Self::UnknownLint,
+    ];
+
+    /// Only warnings can be allowed, warned or denied with `-A`/`-W`/`-D` and `#[allow(...)]`
+    pub fn is_warning(self) -> bool {
+        self.code().starts_with('W')
+    }
+
+    /// Accepts both the code and the name, such as `W0001` and `unused_variable`
+    pub fn from_code_or_name(text: &str) -> Option<DiagnosticCode> {
+        let text = text.trim();
+        Self::ALL
+            .into_iter()
+            .find(|code| code.code().eq_ignore_ascii_case(text) || code.name() == text)
+    }
+
+    /// Short code such as `E0005` or `W0001`
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::SyntaxError => "E0001",
+            Self::NameCollision => "E0002",
+            Self::UnknownName => "E0003",
+            Self::WrongKindOfName => "E0004",
+            Self::TypeError => "E0005",
+            Self::DomainMismatch => "E0006",
+            Self::GenerativeRequired => "E0007",
+            Self::TemplateArguments => "E0008",
+            Self::ArgumentCount => "E0009",
+            Self::InvalidDeclaration => "E0010",
+            Self::InvalidWrite => "E0011",
+            Self::InvalidWireReference => "E0012",
+            Self::InvalidFunctionCall => "E0013",
+            Self::InvalidCross => "E0014",
+            Self::Latency => "E0015",
+            Self::Instantiation => "E0016",
+            Self::GenerativeExecution => "E0017",
+            Self::NotImplemented => "E0018",
+            Self::UnusedVariable => "W0001",
+            Self::UnusedPort => "W0002",
+            Self::UnusedResult => "W0003",
+            Self::StatementsOnSameLine => "W0004",
+            Self::RedundantCross => "W0005",
+            Self::UnknownLint => "W0006",
+        }
+    }
+
+    /// Readable name such as `unused_variable`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::SyntaxError => "syntax_error",
+            Self::NameCollision => "name_collision",
+            Self::UnknownName => "unknown_name",
+            Self::WrongKindOfName => "wrong_kind_of_name",
+            Self::TypeError => "type_error",
+            Self::DomainMismatch => "domain_mismatch",
+            Self::GenerativeRequired => "generative_required",
+            Self::TemplateArguments => "template_arguments",
+            Self::ArgumentCount => "argument_count",
+            Self::InvalidDeclaration => "invalid_declaration",
+            Self::InvalidWrite => "invalid_write",
+            Self::InvalidWireReference => "invalid_wire_reference",
+            Self::InvalidFunctionCall => "invalid_function_call",
+            Self::InvalidCross => "invalid_cross",
+            Self::Latency => "latency",
+            Self::Instantiation => "instantiation",
+            Self::GenerativeExecution => "generative_execution",
+            Self::NotImplemented => "not_implemented",
+            Self::UnusedVariable => "unused_variable",
+            Self::UnusedPort => "unused_port",
+            Self::UnusedResult => "unused_result",
+            Self::StatementsOnSameLine => "statements_on_same_line",
+            Self::RedundantCross => "redundant_cross",
+            Self::UnknownLint => "unknown_lint",
+        }
+    }
+
+    /// One line description of this kind of diagnostic
+    pub fn summary(self) -> &'static str {
+        match self {
+            Self::SyntaxError => "The code could not be parsed",
+            Self::NameCollision => "Multiple things are declared with the same name",
+            Self::UnknownName => "A name, port or interface could not be found",
+            Self::WrongKindOfName => "A name refers to a different kind of object than expected here",
+            Self::TypeError => "Types don't match, or could not be inferred",
+            Self::DomainMismatch => "Wires of different clock domains are combined",
+            Self::GenerativeRequired => "A generative value is required, but a runtime value was given",
+            Self::TemplateArguments => "Template arguments are missing, unknown or given twice",
+            Self::ArgumentCount => "The number of arguments or results doesn't match the function",
+            Self::InvalidDeclaration => "Modifiers or specifiers that aren't allowed on this declaration",
+            Self::InvalidWrite => "Writing to something that can't be written to here",
+            Self::InvalidWireReference => "Something that isn't a wire is used as a wire",
+            Self::InvalidFunctionCall => "Function call syntax used on something that isn't a function",
+            Self::InvalidCross => "Crossing something that can't be crossed into another domain",
+            Self::Latency => "Latency counting found conflicting or undeterminable latencies",
+            Self::Instantiation => "A submodule could not be instantiated with the given arguments",
+            Self::GenerativeExecution => "Executing generative code failed",
+            Self::NotImplemented => "A feature that isn't implemented in the compiler yet",
+            Self::UnusedVariable => "A variable doesn't affect the outputs of its module",
+            Self::UnusedPort => "A port of a submodule is never used",
+            Self::UnusedResult => "The result of an expression is discarded",
+            Self::StatementsOnSameLine => "Multiple declarations or expressions on the same line",
+            Self::RedundantCross => "A wire is crossed into the domain it is already in",
+            Self::UnknownLint => "A lint annotation names an unknown level or diagnostic code",
+        }
+    }
+
+    /// The long explanation shown by `--explain`
+    pub fn explanation(self) -> &'static str {
+        match self {
+            Self::SyntaxError => "\
+The parser could not make sense of the code. The error points at the first token that didn't fit,
+and its info shows the construct that was being parsed. Often a bracket or ';' is missing just before it.",
+            Self::NameCollision => "\
+Two globals, two declarations in the same scope, or two imports share a name.
+Rename one of them. Globals may also not reuse the name of a builtin, such as 'int' or 'bool'.",
+            Self::UnknownName => "\
+No global, local, port or interface of this name exists.
+Check the spelling, and that the file declaring it is part of the compilation.",
+            Self::WrongKindOfName => "\
+The name exists, but refers to something else than is expected here.
+For example a local variable used as a type, or a template type used as a value.",
+            Self::TypeError => "\
+The type of an expression doesn't match the type that is expected, or a type could not be inferred.
+
+    int[4] arr
+    bool b = arr // 'arr' is an int[4], not a bool",
+            Self::DomainMismatch => "\
+Wires of different clock domains are combined without crossing them first.
+Use 'cross' to explicitly move a wire into another domain, and make sure the hardware is safe to do so.",
+            Self::GenerativeRequired => "\
+Generative values are known at compile time. Array sizes, template arguments, for loop bounds and
+writes within generative conditions require them, but a runtime wire was given.",
+            Self::TemplateArguments => "\
+Template arguments must each be given once. Values go before the ';' and types after it, either
+by position or by name:
+
+    FIFO::<DEPTH = 32, READY_SLACK = 3; int> f",
+            Self::ArgumentCount => "\
+A function call passes a different number of arguments than the function has inputs, or assigns its
+results to a different number of targets than it has outputs.",
+            Self::InvalidDeclaration => "\
+The modifiers of a declaration aren't allowed in this place. For example 'input' or 'output' inside a struct,
+'state' on an input, or 'gen' outputs.",
+            Self::InvalidWrite => "\
+The target of a write can't be written to here. Inputs and submodule outputs are read-only,
+globals can't be assigned to, and generative variables can't be written within runtime conditions.",
+            Self::InvalidWireReference => "\
+Something that isn't a wire is used where a wire is needed, such as a module, an interface or the result of an operator
+on the left side of an assignment.",
+            Self::InvalidFunctionCall => "\
+Function call syntax can only be used on modules and interfaces of submodules.",
+            Self::InvalidCross => "\
+Only local wires can be crossed into another domain. Submodules and generative values can't be,
+generative values already exist in every domain.",
+            Self::Latency => "\
+Latency counting assigns each wire an absolute latency. This fails when specified latencies conflict with
+the paths between them, when a port's latency can't be determined, or when part of the module isn't reachable.
+Specifying an explicit latency, such as 'output int o'3', often resolves ambiguities.",
+            Self::Instantiation => "\
+A submodule could not be instantiated with the given template arguments. The errors of the submodule's
+instance explain why. Ports and interfaces that are disabled in the instance also can't be used.",
+            Self::GenerativeExecution => "\
+Executing the generative code of a module failed, for example because an array was indexed out of bounds.",
+            Self::NotImplemented => "\
+This uses a feature of the language that the compiler doesn't support yet.",
+            Self::UnusedVariable => "\
+This variable doesn't affect any of the module's outputs, so it generates no hardware.
+Remove it, or connect it to an output.
+
+    // #[allow(unused_variable)]
+    int debug_counter",
+            Self::UnusedPort => "\
+A port of this submodule is never read or written. The hardware behind an unused output is removed,
+an unused input is left unconnected.",
+            Self::UnusedResult => "\
+The result of this expression is computed, but never used.",
+            Self::StatementsOnSameLine => "\
+Declarations and expressions that aren't assigned to should each be on their own line, for readability.",
+            Self::RedundantCross => "\
+This wire is crossed into the domain it already is in, so the 'cross' does nothing and can be removed.",
+            Self::UnknownLint => "\
+A '#[allow(...)]', '#[warn(...)]' or '#[deny(...)]' annotation in a comment names a level or code that doesn't exist,
+so it has no effect. Codes can be given as 'W0001' or by name, such as 'unused_variable'. Only warnings can be given a level.
+
+    // #[allow(unused_variabel)]
+    int debug_counter",
+        }
+    }
+}
+
+/// Counted while reporting the diagnostics of a compilation, to decide the exit code
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DiagnosticCounts {
+    pub errors: usize,
+    pub warnings: usize,
+    /// Warnings that were turned into errors by `-D`, `--deny-warnings` or `#[deny(...)]`. Also counted in `errors`
+    pub denied_warnings: usize,
+}
+
+impl DiagnosticCounts {
+    pub fn add(&mut self, err: &CompileError) {
+        match err.level {
+            ErrorLevel::Error => {
+                self.errors += 1;
+                if err.code.is_warning() {
+                    self.denied_warnings += 1;
+                }
+            }
+            ErrorLevel::Warning => self.warnings += 1,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ErrorInfo {
     pub position: Span,
@@ -20,11 +289,31 @@ pub struct ErrorInfo {
     pub info: String,
 }
 
+/// A fix for an error that can be applied automatically. Used by the LSP's code actions and `--fix`
+#[derive(Debug, Clone)]
+pub struct SuggestedEdit {
+    /// Always in the same file as the error it belongs to
+    pub position: Span,
+    pub replace_with: String,
+}
+
+impl SuggestedEdit {
+    pub fn description(&self) -> String {
+        if self.replace_with.is_empty() {
+            "Remove this".to_owned()
+        } else {
+            format!("Replace this with \"{}\"", self.replace_with)
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CompileError {
+    pub code: DiagnosticCode,
     pub position: Span,
     pub reason: String,
     pub infos: Vec<ErrorInfo>,
+    pub suggestions: Vec<SuggestedEdit>,
     pub level: ErrorLevel,
 }
 
@@ -122,6 +411,7 @@ impl<'linker> ErrorCollector<'linker> {
     }
     fn push_diagnostic(
         &self,
+        code: DiagnosticCode,
         position: Span,
         reason: String,
         level: ErrorLevel,
@@ -132,9 +422,11 @@ impl<'linker> ErrorCollector<'linker> {
         store.did_error |= level == ErrorLevel::Error;
         let pos = store.errors.len();
         store.errors.push(CompileError {
+            code,
             position,
             reason,
             infos: Vec::new(),
+            suggestions: Vec::new(),
             level,
         });
         ErrorReference {
@@ -143,16 +435,17 @@ impl<'linker> ErrorCollector<'linker> {
         }
     }
 
-    pub fn error<S: Into<String>>(&self, position: Span, reason: S) -> ErrorReference<'_> {
-        self.push_diagnostic(position, reason.into(), ErrorLevel::Error)
+    pub fn error<S: Into<String>>(&self, code: DiagnosticCode, position: Span, reason: S) -> ErrorReference<'_> {
+        self.push_diagnostic(code, position, reason.into(), ErrorLevel::Error)
     }
 
-    pub fn warn<S: Into<String>>(&self, position: Span, reason: S) -> ErrorReference<'_> {
-        self.push_diagnostic(position, reason.into(), ErrorLevel::Warning)
+    pub fn warn<S: Into<String>>(&self, code: DiagnosticCode, position: Span, reason: S) -> ErrorReference<'_> {
+        self.push_diagnostic(code, position, reason.into(), ErrorLevel::Warning)
     }
 
     pub fn todo<S: Into<String>>(&self, position: Span, reason: S) -> ErrorReference<'_> {
         self.push_diagnostic(
+            DiagnosticCode::NotImplemented,
             position,
             format!("TODO: {}", reason.into()),
             ErrorLevel::Error,
@@ -222,13 +515,17 @@ impl<'ec> ErrorReference<'ec> {
         })
     }
     pub fn suggest_replace<S: Into<String>>(&self, replace_span: Span, replace_with: S) -> &Self {
-        self.info_same_file(
-            replace_span,
-            format!("SUGGEST: Replace this with \"{}\"", replace_with.into()),
-        )
+        self.err_collector.assert_span_good(replace_span);
+        self.err_collector.error_store.borrow_mut().errors[self.pos]
+            .suggestions
+            .push(SuggestedEdit {
+                position: replace_span,
+                replace_with: replace_with.into(),
+            });
+        self
     }
     pub fn suggest_remove(&self, remove_span: Span) -> &Self {
-        self.info_same_file(remove_span, "SUGGEST: Remove this")
+        self.suggest_replace(remove_span, "")
     }
 }
 
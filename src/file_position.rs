@@ -140,8 +140,7 @@ impl FileText {
             lines_start_at,
         }
     }
-    /// Errors when byte is outside of file
-    pub fn byte_to_linecol(&self, byte_pos: usize) -> LineCol {
+    fn byte_to_linecol_with(&self, byte_pos: usize, col_width: fn(char) -> usize) -> LineCol {
         assert!(byte_pos <= self.file_text.len());
         let line = match self.lines_start_at.binary_search(&byte_pos) {
             Ok(exact_newline) => exact_newline,
@@ -151,11 +150,19 @@ impl FileText {
 
         LineCol {
             line,
-            col: text_before.chars().count(),
+            col: text_before.chars().map(col_width).sum(),
         }
     }
-    /// Clamps the linecol to be within the file, so cannot error.
-    pub fn linecol_to_byte_clamp(&self, linecol: LineCol) -> usize {
+    /// Errors when byte is outside of file
+    pub fn byte_to_linecol(&self, byte_pos: usize) -> LineCol {
+        self.byte_to_linecol_with(byte_pos, |_| 1)
+    }
+    /// Like [Self::byte_to_linecol], but the column counts UTF-16 code units, as LSP positions do
+    pub fn byte_to_utf16_linecol(&self, byte_pos: usize) -> LineCol {
+        self.byte_to_linecol_with(byte_pos, char::len_utf16)
+    }
+    /// Clamps the linecol to be within the file, so cannot error. The column counts UTF-16 code units, as LSP positions do
+    pub fn utf16_linecol_to_byte_clamp(&self, linecol: LineCol) -> usize {
         let line_end = if linecol.line + 1 < self.lines_start_at.len() {
             self.lines_start_at[linecol.line + 1] - 1
         } else if linecol.line + 1 == self.lines_start_at.len() {
@@ -167,19 +174,41 @@ impl FileText {
         let line_text = &self.file_text[line_start..line_end];
 
         let mut cols_left = linecol.col;
-        let mut char_indices = line_text.char_indices();
-        for (byte, _) in &mut char_indices {
-            if cols_left == 0 {
+        for (byte, c) in line_text.char_indices() {
+            // A column within a character, such as between the two halves of a UTF-16 surrogate pair, clamps to its start
+            if cols_left < c.len_utf16() {
                 return line_start + byte;
             }
-            cols_left -= 1;
+            cols_left -= c.len_utf16();
         }
         line_end
     }
+    /// Like [Self::byte_to_linecol], but the column is counted in bytes, as tree-sitter expects
+    pub fn byte_to_ts_point(&self, byte_pos: usize) -> tree_sitter::Point {
+        assert!(byte_pos <= self.file_text.len());
+        let row = match self.lines_start_at.binary_search(&byte_pos) {
+            Ok(exact_newline) => exact_newline,
+            Err(before_newline) => before_newline - 1,
+        };
+        tree_sitter::Point {
+            row,
+            column: byte_pos - self.lines_start_at[row],
+        }
+    }
+    /// Replaces the text in `byte_range` with `new_text`, and updates the line starts
+    pub fn replace_range(&mut self, byte_range: Range<usize>, new_text: &str) {
+        let mut file_text = std::mem::take(&mut self.file_text);
+        file_text.replace_range(byte_range, new_text);
+        *self = FileText::new(file_text);
+    }
     pub fn get_span_linecol_range(&self, span: Span) -> Range<LineCol> {
         span.debug();
         self.byte_to_linecol(span.0)..self.byte_to_linecol(span.1)
     }
+    pub fn get_span_utf16_linecol_range(&self, span: Span) -> Range<LineCol> {
+        span.debug();
+        self.byte_to_utf16_linecol(span.0)..self.byte_to_utf16_linecol(span.1)
+    }
 
     pub fn is_span_valid(&self, span: Span) -> bool {
         span.debug();
@@ -199,3 +228,53 @@ impl Index<Span> for FileText {
         &self.file_text[index.into_range()]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(row: usize, column: usize) -> tree_sitter::Point {
+        tree_sitter::Point { row, column }
+    }
+
+    #[test]
+    fn replace_range_updates_lines() {
+        let mut text = FileText::new("module a {\n}\n".to_owned());
+        text.replace_range(10..10, "\n\tint x\n");
+        assert_eq!(text.file_text, "module a {\n\tint x\n\n}\n");
+        assert_eq!(text.lines_start_at, vec![0, 11, 18, 19, 21]);
+
+        text.replace_range(10..19, "");
+        assert_eq!(text.file_text, "module a {}\n");
+        assert_eq!(text.lines_start_at, vec![0, 12]);
+    }
+
+    #[test]
+    fn byte_to_ts_point_counts_bytes() {
+        let text = FileText::new("ab\nçd\n".to_owned());
+        assert_eq!(text.byte_to_ts_point(0), point(0, 0));
+        assert_eq!(text.byte_to_ts_point(2), point(0, 2));
+        assert_eq!(text.byte_to_ts_point(3), point(1, 0));
+        // 'ç' is two bytes
+        assert_eq!(text.byte_to_ts_point(5), point(1, 2));
+        assert_eq!(text.byte_to_ts_point(7), point(2, 0));
+    }
+
+    #[test]
+    fn utf16_columns() {
+        // '😀' is 4 bytes, 1 char and 2 UTF-16 code units
+        let text = FileText::new("a😀b\nc".to_owned());
+        let linecol = |line, col| LineCol { line, col };
+
+        assert_eq!(text.utf16_linecol_to_byte_clamp(linecol(0, 3)), 5);
+        // Between the surrogates of '😀'
+        assert_eq!(text.utf16_linecol_to_byte_clamp(linecol(0, 2)), 1);
+        // Past the end of the line
+        assert_eq!(text.utf16_linecol_to_byte_clamp(linecol(0, 100)), 6);
+        assert_eq!(text.utf16_linecol_to_byte_clamp(linecol(1, 1)), 8);
+
+        assert_eq!(text.byte_to_linecol(5).col, 2);
+        assert_eq!(text.byte_to_utf16_linecol(5).col, 3);
+        assert_eq!(text.byte_to_utf16_linecol(8).line, 1);
+    }
+}
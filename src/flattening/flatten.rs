@@ -1,9 +1,10 @@
 use crate::alloc::{ArenaAllocator, UUIDRange, UUID};
 use crate::{alloc::UUIDRangeIter, prelude::*};
 
-use std::
+use std::{
+    collections::HashSet,
     str::FromStr
-;
+};
 
 use num::BigInt;
 use sus_proc_macro::{field, kind, kw};
@@ -11,7 +12,7 @@ use sus_proc_macro::{field, kind, kw};
 use crate::linker::{
     make_resolvers, FileData, NameElem, NameResolver, NamedConstant, Resolver
 };
-use crate::{debug::SpanDebugger, value::Value};
+use crate::{config::ConfigStruct, debug::SpanDebugger, value::Value};
 
 use super::name_context::LocalVariableContext;
 use super::parser::Cursor;
@@ -65,6 +66,7 @@ impl PartialWireReference {
                     .id;
                 ctx.errors
                     .error(
+                        DiagnosticCode::InvalidWireReference,
                         span,
                         "cannot operate on modules directly. Should use ports instead",
                     )
@@ -75,6 +77,7 @@ impl PartialWireReference {
                 let md = &ctx.modules[md_ref.id];
                 ctx.errors
                     .error(
+                        DiagnosticCode::InvalidWireReference,
                         md_ref.span,
                         format!(
                             "Expected a Wire Reference, but found module '{}' instead",
@@ -98,6 +101,7 @@ impl PartialWireReference {
                 let interf = &md.interfaces[interface];
                 ctx.errors
                     .error(
+                        DiagnosticCode::InvalidWireReference,
                         interface_name_span,
                         format!(
                             "Expected a port, but found module interface '{}' instead",
@@ -197,7 +201,8 @@ enum DeclarationContext {
     IO{is_input : bool},
     ForLoopGenerative,
     PlainWire,
-    StructField
+    StructField,
+    ConditionalBinding
 }
 
 struct FlatteningContext<'l, 'errs> {
@@ -208,6 +213,7 @@ struct FlatteningContext<'l, 'errs> {
     constants: Resolver<'l, 'errs, ConstantUUIDMarker, NamedConstant>,
     name_resolver: NameResolver<'l, 'errs>,
     errors: &'errs ErrorCollector<'l>,
+    config: &'l ConfigStruct,
 
     working_on_link_info: &'l LinkInfo,
     instructions: FlatAlloc<Instruction, FlatIDMarker>,
@@ -324,6 +330,7 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                 else {
                     let info = self.name_resolver.get_linking_error_location(name_elem);
                     let err_ref = self.errors.error(
+                        DiagnosticCode::TemplateArguments,
                         name_span,
                         format!(
                             "No template argument of name '{target_name}' on '{}'",
@@ -339,12 +346,12 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                     (TemplateInputKind::Type(_), TemplateArgKind::Type(_))
                     | (TemplateInputKind::Generative(_), TemplateArgKind::Value(_)) => named_index, // OK
                     (TemplateInputKind::Type(_), TemplateArgKind::Value(_)) => {
-                        self.errors.error(name_span, format!("'{target_name}' is not a value. Place it behind the template ';'"))
+                        self.errors.error(DiagnosticCode::TemplateArguments, name_span, format!("'{target_name}' is not a value. Place it behind the template ';'"))
                             .info((input_on_obj.name_span, target_link_info.file), "Declared here");
                         continue;
                     }
                     (TemplateInputKind::Generative(_), TemplateArgKind::Type(_)) => {
-                        self.errors.error(name_span, format!("'{target_name}' is not a type. Place it before the template ';'"))
+                        self.errors.error(DiagnosticCode::TemplateArguments, name_span, format!("'{target_name}' is not a type. Place it before the template ';'"))
                             .info((input_on_obj.name_span, target_link_info.file), "Declared here");
                         continue;
                     }
@@ -356,7 +363,7 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                 };
                 let Some(index) = index else {
                     let info = self.name_resolver.get_linking_error_location(name_elem);
-                    let err_ref = self.errors.error(given_arg.whole_span, format!("Too many template arguments! '{}' only requires {} {kind} template arguments", info.full_name, target_link_info.template_arguments.len()));
+                    let err_ref = self.errors.error(DiagnosticCode::TemplateArguments, given_arg.whole_span, format!("Too many template arguments! '{}' only requires {} {kind} template arguments", info.full_name, target_link_info.template_arguments.len()));
                     if let Some(pos) = info.location {
                         err_ref.info(pos, format!("'{}' declared here", info.full_name));
                     }
@@ -370,6 +377,7 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
             if let Some(existing_arg) = arg {
                 self.errors
                     .error(
+                        DiagnosticCode::TemplateArguments,
                         given_arg.name_specification.unwrap_or(given_arg.whole_span),
                         "This template variable has already been set",
                     )
@@ -402,6 +410,7 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                 let (kind, span) = cursor.kind_span();
                 if let Some(t_span) = &template_args_whole_span {
                     self.errors.error(
+                        DiagnosticCode::TemplateArguments,
                         Span::new_overarching(
                             t_span.outer_span().empty_span_at_end(),
                             total_span.empty_span_at_end(),
@@ -498,6 +507,7 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                 | LocalOrGlobal::Local(span, NamedLocal::SubModule(instr)) => {
                     self.errors
                         .error(
+                            DiagnosticCode::WrongKindOfName,
                             span,
                             format!(
                                 "This is not a {accepted_text}, it is a local variable instead!"
@@ -558,6 +568,7 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
             .add_declaration(&self.name_resolver.file_text[name_span], named_local)
         {
             let err_ref = self.errors.error(
+                DiagnosticCode::NameCollision,
                 name_span,
                 "This declaration conflicts with a previous declaration in the same scope",
             );
@@ -608,13 +619,13 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
             let mut is_port = match declaration_context {
                 DeclarationContext::IO{is_input} => {
                     if let Some((_, io_span)) = io_kw {
-                        self.errors.error(io_span, "Cannot redeclare 'input' or 'output' on functional syntax IO");
+                        self.errors.error(DiagnosticCode::InvalidDeclaration, io_span, "Cannot redeclare 'input' or 'output' on functional syntax IO");
                     }
                     DeclarationPortInfo::RegularPort { is_input, port_id: PortID::PLACEHOLDER }
                 }
                 DeclarationContext::ForLoopGenerative => {
                     if let Some((_, io_span)) = io_kw {
-                        self.errors.error(io_span, "Cannot declare 'input' or 'output' to the iterator of a for loop");
+                        self.errors.error(DiagnosticCode::InvalidDeclaration, io_span, "Cannot declare 'input' or 'output' to the iterator of a for loop");
                     }
                     DeclarationPortInfo::NotPort
                 }
@@ -626,10 +637,16 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                 }
                 DeclarationContext::StructField => {
                     if let Some((_, io_span)) = io_kw {
-                        self.errors.error(io_span, "Cannot declare 'input' or 'output' in a struct");
+                        self.errors.error(DiagnosticCode::InvalidDeclaration, io_span, "Cannot declare 'input' or 'output' in a struct");
                     }
                     DeclarationPortInfo::StructField { field_id: UUID::PLACEHOLDER }
                 }
+                DeclarationContext::ConditionalBinding => {
+                    if let Some((_, io_span)) = io_kw {
+                        self.errors.error(DiagnosticCode::InvalidDeclaration, io_span, "Cannot declare 'input' or 'output' in a conditional binding");
+                    }
+                    DeclarationPortInfo::NotPort
+                }
             };
 
             let identifier_type = match declaration_context {
@@ -637,7 +654,7 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                     match declaration_modifiers {
                         Some((kw!("state"), modifier_span)) => {
                             if is_port.as_regular_port() == Some(true) {
-                                self.errors.error(modifier_span, "Inputs cannot be decorated with 'state'");
+                                self.errors.error(DiagnosticCode::InvalidDeclaration, modifier_span, "Inputs cannot be decorated with 'state'");
                             }
                             IdentifierType::State
                         }
@@ -649,7 +666,7 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                                     is_port = DeclarationPortInfo::GenerativeInput(TemplateID::PLACEHOLDER)
                                 }
                                 DeclarationPortInfo::RegularPort { is_input : false, port_id : _ } => {
-                                    self.errors.error(modifier_span, "Cannot make generative outputs. This is because it could interfere with inference of generic types and generative inputs");
+                                    self.errors.error(DiagnosticCode::InvalidDeclaration, modifier_span, "Cannot make generative outputs. This is because it could interfere with inference of generic types and generative inputs");
                                 }
                                 DeclarationPortInfo::GenerativeInput(_) => unreachable!("Can't have been GenerativeInput here already, because it only gets converted to that here"), 
                             }
@@ -663,10 +680,16 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                 }
                 DeclarationContext::ForLoopGenerative => {
                     if let Some((_, modifier_span)) = declaration_modifiers {
-                        self.errors.error(modifier_span, "Cannot add modifiers to the iterator of a for loop");
+                        self.errors.error(DiagnosticCode::InvalidDeclaration, modifier_span, "Cannot add modifiers to the iterator of a for loop");
                     }
                     IdentifierType::Generative
                 }
+                DeclarationContext::ConditionalBinding => {
+                    if let Some((_, modifier_span)) = declaration_modifiers {
+                        self.errors.error(DiagnosticCode::InvalidDeclaration, modifier_span, "Cannot add modifiers to a conditional binding");
+                    }
+                    IdentifierType::Local
+                }
             };
 
             match &mut is_port {
@@ -697,7 +720,7 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                 ModuleOrWrittenType::Module(module_ref) => {
                     assert!(ALLOW_MODULES);
                     if let Some((_, span)) = span_latency_specifier {
-                        self.errors.error(span, "Cannot add latency specifier to module instances");
+                        self.errors.error(DiagnosticCode::InvalidDeclaration, span, "Cannot add latency specifier to module instances");
                     }
                     let name = &self.name_resolver.file_text[name_span];
 
@@ -786,14 +809,14 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                     let excess_args_span = Span::new_overarching(self.instructions[arguments[expected_arg_count]].unwrap_wire().span, self.instructions[*arguments.last().unwrap()].unwrap_wire().span);
 
                     self.errors
-                        .error(excess_args_span, format!("Excess argument. Function takes {expected_arg_count} args, but {arg_count} were passed."))
+                        .error(DiagnosticCode::ArgumentCount, excess_args_span, format!("Excess argument. Function takes {expected_arg_count} args, but {arg_count} were passed."))
                         .info_obj(&(md, interface));
                     // Shorten args to still get proper type checking for smaller arg array
                     arguments.truncate(expected_arg_count);
                 } else {
                     // Too few args, mention missing argument names
                     self.errors
-                        .error(arguments_span.close_bracket(), format!("Too few arguments. Function takes {expected_arg_count} args, but {arg_count} were passed."))
+                        .error(DiagnosticCode::ArgumentCount, arguments_span.close_bracket(), format!("Too few arguments. Function takes {expected_arg_count} args, but {arg_count} were passed."))
                         .info_obj(&(md, interface));
 
                     while arguments.len() < expected_arg_count {
@@ -866,6 +889,7 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
             }),
             PartialWireReference::WireReference(_wire_ref) => {
                 self.errors.error(
+                    DiagnosticCode::InvalidFunctionCall,
                     outer_span,
                     "Function call syntax is only possible on modules or interfaces of modules",
                 );
@@ -920,7 +944,7 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                 let (md, interface) = self.get_interface_reference(&fc.interface_reference);
                 if interface.func_call_outputs.len() != 1 {
                     self.errors
-                        .error(expr_span, "A function called in this context may only return one result. Split this function call into a separate line instead.")
+                        .error(DiagnosticCode::ArgumentCount, expr_span, "A function called in this context may only return one result. Split this function call into a separate line instead.")
                         .info_obj(&(md, interface));
                 }
 
@@ -944,6 +968,26 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
             return cursor.go_down_content(kind!("parenthesis_expression"), |cursor| {
                 self.flatten_expr(cursor)
             });
+        } else if kind == kind!("array_literal") {
+            let elements = cursor.collect_list(kind!("array_literal"), |cursor| {
+                self.flatten_expr(cursor)
+            });
+            if elements.is_empty() {
+                self.errors.error(DiagnosticCode::TypeError, expr_span, "Empty array literals are not allowed, because their type cannot be inferred");
+                WireSource::new_error()
+            } else {
+                WireSource::ArrayLiteral(elements)
+            }
+        } else if kind == kind!("array_repeat") {
+            cursor.go_down_no_check(|cursor| {
+                cursor.field(field!("element"));
+                let element = self.flatten_expr(cursor);
+
+                cursor.field(field!("count"));
+                let count = self.flatten_expr(cursor);
+
+                WireSource::ArrayRepeat { element, count }
+            })
         } else {
             if let Some(wr) = self.flatten_wire_reference(cursor).expect_wireref(self) {
                 WireSource::WireRef(wr)
@@ -978,6 +1022,7 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                     }
                     NamedLocal::TemplateType(template_id) => {
                         self.errors.error(
+                            DiagnosticCode::WrongKindOfName,
                             span,
                             format!(
                                 "Expected a value, but instead found template type '{}'",
@@ -1055,11 +1100,11 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                 match flattened_arr_expr {
                     PartialWireReference::Error => PartialWireReference::Error,
                     PartialWireReference::GlobalModuleName(md_ref) => {
-                        self.errors.error(md_ref.span, "Ports or interfaces can only be accessed on modules that have been explicitly declared. Declare this submodule on its own line");
+                        self.errors.error(DiagnosticCode::InvalidWireReference, md_ref.span, "Ports or interfaces can only be accessed on modules that have been explicitly declared. Declare this submodule on its own line");
                         PartialWireReference::Error
                     }
                     PartialWireReference::ModuleWithInterface { submodule_decl:_, submodule_name_span, interface:_, interface_name_span } => {
-                        self.errors.error(port_name_span, "Omit the interface when accessing a port")
+                        self.errors.error(DiagnosticCode::InvalidWireReference, port_name_span, "Omit the interface when accessing a port")
                             .suggest_remove(Span::new_overarching(submodule_name_span.empty_span_at_end(), interface_name_span));
 
                         PartialWireReference::Error
@@ -1090,37 +1135,44 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                         }
                     }
                     PartialWireReference::WireReference(_) => {
-                        println!("TODO: Struct fields");
+                        log!(self.config, Warn, Flatten, "TODO: Struct fields");
                         PartialWireReference::Error
                     }
                 }
             })
         } else if kind == kind!("number") {
             self.errors
-                .error(expr_span, "A constant is not a wire reference");
+                .error(DiagnosticCode::InvalidWireReference, expr_span, "A constant is not a wire reference");
             PartialWireReference::Error
         } else if kind == kind!("unary_op") {
             self.errors.error(
+                DiagnosticCode::InvalidWireReference,
                 expr_span,
                 "The result of an operator is not a wire reference",
             );
             PartialWireReference::Error
         } else if kind == kind!("binary_op") {
             self.errors.error(
+                DiagnosticCode::InvalidWireReference,
                 expr_span,
                 "The result of an operator is not a wire reference",
             );
             PartialWireReference::Error
         } else if kind == kind!("func_call") {
             self.errors
-                .error(expr_span, "A submodule call is not a wire reference");
+                .error(DiagnosticCode::InvalidWireReference, expr_span, "A submodule call is not a wire reference");
             PartialWireReference::Error
         } else if kind == kind!("parenthesis_expression") {
             self.errors.error(
+                DiagnosticCode::InvalidWireReference,
                 expr_span,
                 "Parentheses are not allowed within a wire reference",
             );
             PartialWireReference::Error
+        } else if kind == kind!("array_literal") || kind == kind!("array_repeat") {
+            self.errors
+                .error(DiagnosticCode::InvalidWireReference, expr_span, "An array literal is not a wire reference");
+            PartialWireReference::Error
         } else {
             cursor.could_not_match()
         }
@@ -1129,7 +1181,26 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
     fn flatten_if_statement(&mut self, cursor: &mut Cursor) {
         cursor.go_down(kind!("if_statement"), |cursor| {
             cursor.field(field!("condition"));
-            let condition = self.flatten_expr(cursor);
+            let (condition_kind, condition_span) = cursor.kind_span();
+
+            // Conditional bindings are only visible within the then block
+            let bindings_frame = self.local_variable_context.new_frame();
+            let (condition, binding_writes) = if condition_kind == kind!("func_call") {
+                let fc_id = self.flatten_func_call(cursor);
+                let bindings = if cursor.optional_field(field!("conditional_bindings")) {
+                    self.flatten_conditional_bindings(cursor)
+                } else {
+                    Vec::new()
+                };
+                self.flatten_condition_func_call(fc_id, &bindings, condition_span)
+            } else {
+                let condition = self.flatten_expr(cursor);
+                if cursor.optional_field(field!("conditional_bindings")) {
+                    self.errors.error(DiagnosticCode::InvalidFunctionCall, cursor.span(), "Conditional bindings require a function call as the condition");
+                    self.flatten_conditional_bindings(cursor);
+                }
+                (condition, Vec::new())
+            };
 
             let if_id = self.instructions.alloc(Instruction::IfStatement(IfStatement {
                 condition,
@@ -1139,8 +1210,14 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
             }));
             let then_start = self.instructions.get_next_alloc_id();
 
+            // The bindings are only written when the condition holds
+            for (from, binding) in binding_writes {
+                self.write_conditional_binding(from, binding);
+            }
+
             cursor.field(field!("then_block"));
             self.flatten_code(cursor);
+            self.local_variable_context.pop_frame(bindings_frame);
 
             let then_end_else_start = self.instructions.get_next_alloc_id();
             if cursor.optional_field(field!("else_block")) {
@@ -1161,6 +1238,99 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
         })
     }
 
+    /// The declarations after the ':' in `if iter.next() : int value {...}`
+    fn flatten_conditional_bindings(&mut self, cursor: &mut Cursor) -> Vec<FlatID> {
+        cursor.collect_list(kind!("declaration_list"), |cursor| {
+            self.flatten_declaration::<false>(DeclarationContext::ConditionalBinding, false, true, cursor)
+        })
+    }
+
+    /// The first output of the function becomes the condition, the remaining outputs are returned together with the conditional binding they must be written to.
+    /// The writes belong in the then block, see [Self::write_conditional_binding]
+    /// 
+    /// Without bindings, the function may only return the condition, like in any other expression
+    fn flatten_condition_func_call(
+        &mut self,
+        fc_id: Option<FlatID>,
+        bindings: &[FlatID],
+        condition_span: Span,
+    ) -> (FlatID, Vec<(FlatID, FlatID)>) {
+        let Some(fc_id) = fc_id else {
+            // Function desugaring or using threw an error
+            return (self.alloc_error(condition_span), Vec::new());
+        };
+        let fc = self.instructions[fc_id].unwrap_func_call();
+
+        let (md, interface) = self.get_interface_reference(&fc.interface_reference);
+        let outputs = interface.func_call_outputs;
+        let submodule_name_span = fc.interface_reference.name_span;
+        let submodule_decl = fc.interface_reference.submodule_decl;
+
+        if outputs.len() != bindings.len() + 1 {
+            if bindings.is_empty() {
+                self.errors
+                    .error(DiagnosticCode::ArgumentCount, condition_span, "A function called in this context may only return one result. Split this function call into a separate line instead, or bind the other results with 'if func() : int a, int b {...}'")
+                    .info_obj(&(md, interface));
+            } else {
+                self.errors
+                    .error(DiagnosticCode::ArgumentCount, condition_span, format!("A function with conditional bindings must return a condition, followed by one result per binding. This function returns {} results, but {} bindings were given.", outputs.len(), bindings.len()))
+                    .info_obj(&(md, interface));
+            }
+        }
+
+        let mut outputs_iter = outputs.into_iter();
+        let Some(condition_port) = outputs_iter.next() else {
+            return (self.alloc_error(condition_span), Vec::new());
+        };
+        let condition = self.alloc_func_call_output(condition_port, submodule_name_span, submodule_decl, condition_span);
+
+        let binding_writes = outputs_iter
+            .zip(bindings)
+            .map(|(port, binding)| {
+                let from = self.alloc_func_call_output(port, submodule_name_span, submodule_decl, condition_span);
+                (from, *binding)
+            })
+            .collect();
+
+        (condition, binding_writes)
+    }
+
+    fn write_conditional_binding(&mut self, from: FlatID, binding: FlatID) {
+        let binding_name_span = self.instructions[binding].unwrap_wire_declaration().name_span;
+        self.instructions.alloc(Instruction::Write(Write {
+            from,
+            to: WireReference {
+                root: WireReferenceRoot::LocalDecl(binding, binding_name_span),
+                path: Vec::new(),
+            },
+            to_span: binding_name_span,
+            write_modifiers: WriteModifiers::Connection {
+                num_regs: 0,
+                regs_span: binding_name_span.empty_span_at_front(),
+            },
+        }));
+    }
+
+    fn alloc_func_call_output(
+        &mut self,
+        port: PortID,
+        submodule_name_span: Option<Span>,
+        submodule_decl: FlatID,
+        func_call_span: Span,
+    ) -> FlatID {
+        self.instructions.alloc(Instruction::Wire(WireInstance {
+            typ: FullType::new_unset(),
+            span: func_call_span,
+            source: WireSource::WireRef(WireReference::simple_port(PortInfo {
+                port,
+                port_name_span: None,
+                is_input: false,
+                submodule_name_span,
+                submodule_decl,
+            })),
+        }))
+    }
+
     fn flatten_assign_function_call(
         &mut self,
         to: Vec<(Option<(WireReference, WriteModifiers)>, Span)>,
@@ -1183,11 +1353,11 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                     let excess_results_span =
                         Span::new_overarching(to[num_func_outputs].1, to.last().unwrap().1);
                     self.errors
-                        .error(excess_results_span, format!("Excess output targets. Function returns {num_func_outputs} results, but {num_targets} targets were given."))
+                        .error(DiagnosticCode::ArgumentCount, excess_results_span, format!("Excess output targets. Function returns {num_func_outputs} results, but {num_targets} targets were given."))
                         .info_obj(&(md, interface));
                 } else {
                     self.errors
-                        .error(func_call_span, format!("Too few output targets. Function returns {num_func_outputs} results, but {num_targets} targets were given."))
+                        .error(DiagnosticCode::ArgumentCount, func_call_span, format!("Too few output targets. Function returns {num_func_outputs} results, but {num_targets} targets were given."))
                         .info_obj(&(md, interface));
                 }
             }
@@ -1195,18 +1365,7 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
             let mut to_iter = to.into_iter();
             for port in outputs {
                 if let Some((Some((to, write_modifiers)), to_span)) = to_iter.next() {
-                    let from =
-                        self.instructions.alloc(Instruction::Wire(WireInstance {
-                            typ: FullType::new_unset(),
-                            span: func_call_span,
-                            source: WireSource::WireRef(WireReference::simple_port(PortInfo {
-                                port,
-                                port_name_span: None,
-                                is_input: false,
-                                submodule_name_span,
-                                submodule_decl,
-                            })),
-                        }));
+                    let from = self.alloc_func_call_output(port, submodule_name_span, submodule_decl, func_call_span);
                     self.instructions.alloc(Instruction::Write(Write {
                         from,
                         to,
@@ -1264,7 +1423,7 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                         let read_side = self.flatten_expr(cursor);
 
                         if to.len() != 1 {
-                            self.errors.error(span, format!("Non-function assignments must output exactly 1 output instead of {}", to.len()));
+                            self.errors.error(DiagnosticCode::ArgumentCount, span, format!("Non-function assignments must output exactly 1 output instead of {}", to.len()));
                         }
                         if let Some((Some((to, write_modifiers)), to_span)) = to.into_iter().next() {
                             self.instructions.alloc(Instruction::Write(Write{from: read_side, to, to_span, write_modifiers}));
@@ -1312,6 +1471,8 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                         self.flatten_interface_ports(cursor);
                     }
                 });
+            } else if kind == kind!("cross_statement") {
+                self.flatten_cross_statement(cursor);
             } else if kind == kind!("domain_statement") {
                 // Skip, because we already covered domains in initialization. 
                 // TODO synchronous & async clocks
@@ -1322,6 +1483,59 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
         });
     }
 
+    /// `cross a, b, c` replaces each named wire with a read-only copy in a new domain.
+    /// 
+    /// The copies share a `group_leader`, such that latency counting keeps them aligned with each other
+    fn flatten_cross_statement(&mut self, cursor: &mut Cursor) {
+        let mut group_leader = None;
+        cursor.list(kind!("cross_statement"), |cursor| {
+            let name_span = cursor.span();
+            let name = &self.name_resolver.file_text[name_span];
+            let from = match self.local_variable_context.get_declaration_for(name) {
+                Some(NamedLocal::Declaration(from)) => from,
+                Some(NamedLocal::SubModule(submod_id)) => {
+                    self.errors
+                        .error(DiagnosticCode::InvalidCross, name_span, "Submodules cannot be crossed, only wires can")
+                        .info_obj_same_file(self.instructions[submod_id].unwrap_submodule());
+                    return;
+                }
+                None => {
+                    self.errors.error(DiagnosticCode::InvalidCross, name_span, format!("No local wire named '{name}' to cross"));
+                    return;
+                }
+            };
+            let original = self.instructions[from].unwrap_wire_declaration();
+            if original.identifier_type == IdentifierType::Generative {
+                self.errors
+                    .error(DiagnosticCode::InvalidCross, name_span, "Generative values exist in every domain, they don't need to be crossed")
+                    .info_obj_same_file(original);
+                return;
+            }
+            let typ_expr = original.typ_expr.clone();
+            let documentation = cursor.extract_gathered_comments();
+
+            let decl_id = self.instructions.get_next_alloc_id();
+            let group_leader = *group_leader.get_or_insert(decl_id);
+
+            self.instructions.alloc(Instruction::Declaration(Declaration{
+                typ_expr,
+                typ : FullType::new_unset(),
+                read_only : true,
+                declaration_itself_is_not_written_to : true,
+                is_port : DeclarationPortInfo::Crossed { from, group_leader },
+                identifier_type : IdentifierType::Local,
+                name : name.to_owned(),
+                name_span,
+                decl_span : name_span,
+                declaration_runtime_depth : DECL_DEPTH_LATER,
+                latency_specifier : None,
+                documentation
+            }));
+
+            self.local_variable_context.shadow_declaration(name, NamedLocal::Declaration(decl_id));
+        });
+    }
+
     fn flatten_write_modifiers(&self, cursor: &mut Cursor) -> WriteModifiers {
         if cursor.optional_field(field!("write_modifiers")) {
             let modifiers_span = cursor.span();
@@ -1407,16 +1621,20 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
     /// - Standalone declarations:
     ///     Yes modules, No write modifiers, Yes expressions (-> single expressions)
     fn flatten_standalone_decls(&mut self, cursor: &mut Cursor) {
-        let mut is_first_item = true;
+        let mut previous_item: Option<Span> = None;
         cursor.list(kind!("assign_left_side"), |cursor| {
+            let item_span = cursor.span();
             cursor.go_down(kind!("assign_to"), |cursor| {
-                if !is_first_item {
-                    self.errors.warn(cursor.span(), "Standalone declarations and expressions should be on their own line.");
+                if let Some(previous_item) = previous_item {
+                    let separator = Span::new_overarching(previous_item.empty_span_at_end(), item_span.empty_span_at_front());
+                    let new_line = format!("\n{}", self.indentation_of_line(previous_item));
+                    self.errors.warn(DiagnosticCode::StatementsOnSameLine, cursor.span(), "Standalone declarations and expressions should be on their own line.")
+                        .suggest_replace(separator, new_line);
                 }
-                is_first_item = false;
 
                 if let Some(span) = cursor.optional_field_span(field!("write_modifiers"), kind!("write_modifiers")) {
-                    self.errors.error(span, "No write modifiers are allowed on non-assigned to declarations or expressions");
+                    self.errors.error(DiagnosticCode::InvalidWrite, span, "No write modifiers are allowed on non-assigned to declarations or expressions")
+                        .suggest_remove(span);
                 }
 
                 cursor.field(field!("expr_or_decl"));
@@ -1428,14 +1646,22 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
                     if kind == kind!("func_call") {
                         self.flatten_assign_function_call(Vec::new(), cursor);
                     } else {
-                        self.errors.warn(span, "The result of this operation is not used");
+                        self.errors.warn(DiagnosticCode::UnusedResult, span, "The result of this operation is not used");
                         let _ = self.flatten_expr(cursor);
                     }
                 }
             });
+            previous_item = Some(item_span);
         })
     }
 
+    /// The whitespace at the start of the line that `span` starts on
+    fn indentation_of_line(&self, span: Span) -> &str {
+        let text_before = &self.name_resolver.file_text.file_text[..span.into_range().start];
+        let line = &text_before[text_before.rfind('\n').map_or(0, |newline| newline + 1)..];
+        &line[..line.len() - line.trim_start().len()]
+    }
+
     fn flatten_declaration_list(
         &mut self,
         declaration_context: DeclarationContext,
@@ -1467,7 +1693,7 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
         let name_span = cursor.field_span(field!("name"), kind!("identifier"));
         self.flatten_template_inputs(cursor);
         let module_name = &self.name_resolver.file_text[name_span];
-        println!("TREE SITTER module! {module_name}");
+        log!(self.config, Debug, Flatten, "Flattening module {module_name}");
         // Interface is allocated in self
         if cursor.optional_field(field!("interface_ports")) {
             self.flatten_interface_ports(cursor);
@@ -1481,10 +1707,14 @@ impl<'l, 'errs> FlatteningContext<'l, 'errs> {
 /// Flattens all modules in the project.
 ///
 /// Requires that first, all modules have been initialized.
-pub fn flatten_all_modules(linker: &mut Linker) {
+/// Only flattens the globals in `to_flatten`, the others are left as they are
+pub fn flatten_all_modules(linker: &mut Linker, to_flatten: &HashSet<NameElem>) {
     let linker_files : *const ArenaAllocator<FileData, FileUUIDMarker> = &linker.files;
     // SAFETY we won't be touching the files anywere. This is just to get the compiler to stop complaining about linker going into the closure. 
     for (_file_id, file) in unsafe{&*linker_files} {
+        if !file.associated_values.iter().any(|global| to_flatten.contains(global)) {
+            continue;
+        }
         let mut span_debugger = SpanDebugger::new("flatten_all_modules", file);
         let mut associated_value_iter = file.associated_values.iter();
 
@@ -1493,6 +1723,9 @@ pub fn flatten_all_modules(linker: &mut Linker) {
         cursor.list(kind!("source_file"), |cursor| {
             cursor.go_down(kind!("global_object"), |cursor| {
                 let file_obj = *associated_value_iter.next().expect("Iterator cannot be exhausted");
+                if !to_flatten.contains(&file_obj) {
+                    return;
+                }
                 let (obj_link_info_mut, ports_to_visit, fields_to_visit, default_declaration_context) = match file_obj {
                     NameElem::Module(module_uuid) => {
                         let md = &mut linker.modules[module_uuid];
@@ -1518,6 +1751,7 @@ pub fn flatten_all_modules(linker: &mut Linker) {
                     default_declaration_context,
                     template_inputs_to_visit,
                     errors: name_resolver.errors,
+                    config: &linker.config,
                     working_on_link_info: linker.get_link_info(file_obj).unwrap(),
                     instructions: FlatAlloc::new(),
                     modules,
@@ -1554,7 +1788,8 @@ pub fn flatten_all_modules(linker: &mut Linker) {
                     
                                         *declaration_instruction = decl_id;
                                     }
-                                    DeclarationPortInfo::StructField { field_id:_ } => unreachable!("No Struct fields in Modules")
+                                    DeclarationPortInfo::StructField { field_id:_ } => unreachable!("No Struct fields in Modules"),
+                                    DeclarationPortInfo::Crossed { from:_, group_leader:_ } => {}
                                 }
                             }
                         }
@@ -1574,6 +1809,7 @@ pub fn flatten_all_modules(linker: &mut Linker) {
                                         field.declaration_instruction = decl_id;
                                     }
                                     DeclarationPortInfo::RegularPort { is_input:_, port_id:_ } => {unreachable!("No ports in structs")}
+                                    DeclarationPortInfo::Crossed { from:_, group_leader:_ } => {unreachable!("No cross statements in structs")}
                                     DeclarationPortInfo::GenerativeInput(this_template_id) => {
                                         let TemplateInputKind::Generative(GenerativeTemplateInputKind { decl_span:_, declaration_instruction }) = 
                                             &mut typ.link_info.template_arguments[this_template_id].kind else {unreachable!()};
@@ -1594,3 +1830,65 @@ pub fn flatten_all_modules(linker: &mut Linker) {
         span_debugger.defuse();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler_top::test_util::{compile, error_messages};
+
+    const SOURCE: &str = "module Source {
+        interface next : -> bool valid, int data
+        valid = true
+        data = 3
+    }
+    ";
+
+    fn errors_of(user: &str) -> Vec<String> {
+        let (linker, file_id) = compile(&format!("{SOURCE}{user}"));
+        error_messages(&linker, file_id)
+    }
+
+    fn is_unknown_value(error: &String) -> bool {
+        error == "No Global of the name 'value' was found. Did you forget to import it?"
+    }
+
+    #[test]
+    fn conditional_binding_is_readable_in_the_then_block() {
+        let errors = errors_of(
+            "module User {
+                interface User : -> int o
+                Source src
+                if src.next() : int value {
+                    o = value
+                }
+            }",
+        );
+        assert!(!errors.iter().any(is_unknown_value), "{errors:?}");
+    }
+
+    #[test]
+    fn conditional_binding_is_not_readable_after_the_if() {
+        let errors = errors_of(
+            "module User {
+                interface User : -> int o
+                Source src
+                if src.next() : int value {}
+                o = value
+            }",
+        );
+        assert!(errors.iter().any(is_unknown_value), "{errors:?}");
+    }
+
+    #[test]
+    fn conditional_binding_is_not_readable_in_the_else_block() {
+        let errors = errors_of(
+            "module User {
+                interface User : -> int o
+                Source src
+                if src.next() : int value {} else {
+                    o = value
+                }
+            }",
+        );
+        assert!(errors.iter().any(is_unknown_value), "{errors:?}");
+    }
+}
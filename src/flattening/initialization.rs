@@ -21,6 +21,7 @@ struct InitializationContext<'linker> {
     ports: FlatAlloc<Port, PortIDMarker>,
     interfaces: FlatAlloc<Interface, InterfaceIDMarker>,
     domains: FlatAlloc<String, DomainIDMarker>,
+    domain_spans: FlatAlloc<Span, DomainIDMarker>,
     
     // struct-only stuff
     fields: FlatAlloc<StructField, FieldIDMarker>,
@@ -33,6 +34,7 @@ impl<'linker> InitializationContext<'linker> {
         let name_span = cursor.field_span(field!("name"), kind!("identifier"));
         let name = self.file_text[name_span].to_owned();
         self.domains.alloc(name.clone());
+        self.domain_spans.alloc(name_span);
         if cursor.optional_field(field!("template_declaration_arguments")) {
             cursor.list(kind!("template_declaration_arguments"), |cursor| {
                 cursor.go_down(kind!("template_declaration_type"), |cursor| {
@@ -56,6 +58,7 @@ impl<'linker> InitializationContext<'linker> {
     fn gather_ports_in_if_stmt(&mut self, cursor: &mut Cursor) {
         cursor.go_down_no_check(|cursor| {
             cursor.field(field!("condition"));
+            let _ = cursor.optional_field(field!("conditional_bindings"));
             cursor.field(field!("then_block"));
             self.gather_all_ports_in_block(cursor);
             if cursor.optional_field(field!("else_block")) {
@@ -103,7 +106,8 @@ impl<'linker> InitializationContext<'linker> {
                         let domain_name_span =
                             cursor.field_span(field!("name"), kind!("identifier"));
                         let name = &self.file_text[domain_name_span];
-                        self.domains.alloc(name.to_owned())
+                        self.domains.alloc(name.to_owned());
+                        self.domain_spans.alloc(domain_name_span);
                     });
                 }
                 kind!("interface_statement") => {
@@ -278,6 +282,7 @@ fn initialize_global_object(builder: &mut FileBuilder, parsing_errors: ErrorColl
         ports: FlatAlloc::new(),
         interfaces: FlatAlloc::new(),
         domains: FlatAlloc::new(),
+        domain_spans: FlatAlloc::new(),
         template_inputs: FlatAlloc::new(),
         fields: FlatAlloc::new(),
         file_text: &builder.file_data.file_text,
@@ -311,6 +316,7 @@ fn initialize_global_object(builder: &mut FileBuilder, parsing_errors: ErrorColl
                 instructions: FlatAlloc::new(),
                 ports: ctx.ports,
                 domain_names: ctx.domains,
+                domain_name_spans: ctx.domain_spans,
                 domains: FlatAlloc::new(),
                 interfaces: ctx.interfaces,
                 instantiations: InstantiationList::new(),
@@ -55,6 +55,10 @@ pub struct Module {
 
     /// Created in Stage 1: Initialization
     pub domain_names: FlatAlloc<String, DomainIDMarker>,
+    /// Created in Stage 1: Initialization
+    ///
+    /// The first domain is named after the module, so its span is the module's name
+    pub domain_name_spans: FlatAlloc<Span, DomainIDMarker>,
 
     /// Created in Stage 1: Initialization
     pub interfaces: FlatAlloc<Interface, InterfaceIDMarker>,
@@ -104,6 +108,7 @@ impl Module {
         }
         errors
             .error(
+                DiagnosticCode::UnknownName,
                 name_span,
                 format!(
                     "There is no port or interface of name '{name_text}' on module {}",
@@ -370,6 +375,13 @@ pub enum WireSource {
         right: FlatID,
     },
     Constant(Value),
+    /// `[a, b, c]`. Is never empty
+    ArrayLiteral(Vec<FlatID>),
+    /// `[element; count]`. `count` must be generative
+    ArrayRepeat {
+        element: FlatID,
+        count: FlatID,
+    },
 }
 
 impl WireSource {
@@ -378,7 +390,7 @@ impl WireSource {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum WrittenType {
     Error(Span),
     Template(Span, TemplateID),
@@ -436,6 +448,9 @@ pub enum DeclarationPortInfo {
     StructField { field_id : FieldID },
     RegularPort { is_input: bool, port_id: PortID },
     GenerativeInput(TemplateID),
+    /// Created by a `cross` statement. `from` is the declaration that was crossed,
+    /// `group_leader` is the first crossed declaration of the same statement, all of which keep their relative latency
+    Crossed { from: FlatID, group_leader: FlatID },
 }
 
 impl DeclarationPortInfo {
@@ -459,6 +474,7 @@ impl DeclarationPortInfo {
                 port_id: _,
             } => *is_input,
             DeclarationPortInfo::GenerativeInput(_) => true,
+            DeclarationPortInfo::Crossed { from: _, group_leader: _ } => true,
         }
     }
 }
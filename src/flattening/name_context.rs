@@ -26,6 +26,10 @@ impl<'file, Obj: Copy> LocalVariableContext<'file, Obj> {
         self.local_stack.push((new_local_name, new_local_unique_id));
         Ok(())
     }
+    /// Rebinds an existing name to a new object. Used by `cross`, where the crossed wire takes over the original's name
+    pub fn shadow_declaration(&mut self, local_name: &'file str, new_local_unique_id: Obj) {
+        self.local_stack.push((local_name, new_local_unique_id));
+    }
     pub fn new_initial() -> Self {
         Self {
             local_stack: Vec::new(),
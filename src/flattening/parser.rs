@@ -24,9 +24,9 @@ fn print_current_node_indented<'ft>(file_text: &'ft FileText, cursor: &TreeCurso
     let cursor_span = Span::from(n.byte_range());
     let node_name = get_readable_node_name(file_text, kind, cursor_span);
     if let Some(field_name) = cursor.field_name() {
-        println!("{indent} {field_name}: {node_name} [{cursor_span}]");
+        eprintln!("{indent} {field_name}: {node_name} [{cursor_span}]");
     } else {
-        println!("{indent} {node_name} [{cursor_span}]");
+        eprintln!("{indent} {node_name} [{cursor_span}]");
     }
     node_name
 }
@@ -67,14 +67,14 @@ impl<'t> Cursor<'t> {
     pub fn print_stack(&mut self) {
         let this_node_kind = self.cursor.node().kind();
         let this_node_span = self.span();
-        println!("Stack:");
+        eprintln!("Stack:");
         loop {
             print_current_node_indented(self.file_text, &self.cursor);
             if !self.cursor.goto_parent() {
                 break;
             }
         }
-        println!("Current node: {this_node_kind}, {this_node_span}");
+        eprintln!("Current node: {this_node_kind}, {this_node_span}");
     }
 
     #[track_caller]
@@ -319,6 +319,7 @@ impl<'t> Cursor<'t> {
             let parent_node_name = parent_node.kind();
             errors
                 .error(
+                    DiagnosticCode::SyntaxError,
                     span,
                     format!(
                         "While parsing '{parent_node_name}', parser found a {error_type} {of_name}"
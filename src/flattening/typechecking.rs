@@ -1,5 +1,6 @@
 use crate::prelude::*;
 
+use std::collections::HashSet;
 use std::ops::{Deref, DerefMut};
 
 use walk::for_each_generative_input_in_template_args;
@@ -16,11 +17,15 @@ use crate::typing::{
 
 use super::*;
 
-pub fn typecheck_all_modules(linker: &mut Linker) {
+/// Only typechecks the modules in `to_typecheck`
+pub fn typecheck_all_modules(linker: &mut Linker, to_typecheck: &HashSet<NameElem>) {
     let linker_ptr: *mut Linker = linker;
     for (module_uuid, module) in &mut linker.modules {
+        if !to_typecheck.contains(&NameElem::Module(module_uuid)) {
+            continue;
+        }
         let ctx_info_string = format!("Typechecking {}", &module.link_info.name);
-        println!("{ctx_info_string}");
+        log!(linker.config, Debug, Flatten, "{ctx_info_string}");
         let mut span_debugger = SpanDebugger::new(
             &ctx_info_string,
             &linker.files[module.link_info.file],
@@ -197,13 +202,13 @@ impl<'l, 'errs> TypeCheckingContext<'l, 'errs> {
                         let decl = self.working_on.instructions[decl_id].unwrap_wire_declaration();
                         if decl.read_only {
                             self.errors
-                                .error(conn.to_span, format!("'{}' is read-only", decl.name))
+                                .error(DiagnosticCode::InvalidWrite, conn.to_span, format!("'{}' is read-only", decl.name))
                                 .info_obj_same_file(decl);
                         }
                         (decl, self.errors.file)
                     }
                     WireReferenceRoot::NamedConstant(_, span) => {
-                        self.errors.error(span, "Cannot assign to a global");
+                        self.errors.error(DiagnosticCode::InvalidWrite, span, "Cannot assign to a global");
                         return;
                     }
                     WireReferenceRoot::SubModulePort(port) => {
@@ -211,7 +216,7 @@ impl<'l, 'errs> TypeCheckingContext<'l, 'errs> {
 
                         if !r.0.is_port.as_regular_port().unwrap() {
                             self.errors
-                                .error(conn.to_span, "Cannot assign to a submodule output port")
+                                .error(DiagnosticCode::InvalidWrite, conn.to_span, "Cannot assign to a submodule output port")
                                 .info_obj_different_file(r.0, r.1);
                         }
 
@@ -233,7 +238,7 @@ impl<'l, 'errs> TypeCheckingContext<'l, 'errs> {
                                 if self.runtime_condition_stack.len()
                                     > to_decl.declaration_runtime_depth
                                 {
-                                    let err_ref = self.errors.error(conn.to_span, "Cannot write to generative variables in runtime conditional block");
+                                    let err_ref = self.errors.error(DiagnosticCode::InvalidWrite, conn.to_span, "Cannot write to generative variables in runtime conditional block");
                                     err_ref.info_obj_different_file(decl, file);
                                     for elem in &self.runtime_condition_stack
                                         [to_decl.declaration_runtime_depth..]
@@ -248,6 +253,7 @@ impl<'l, 'errs> TypeCheckingContext<'l, 'errs> {
                         if decl.identifier_type != IdentifierType::State {
                             self.errors
                                 .error(
+                                    DiagnosticCode::InvalidDeclaration,
                                     initial_kw_span,
                                     "Initial values can only be given to state registers",
                                 )
@@ -349,7 +355,7 @@ impl<'l, 'errs> TypeCheckingContext<'l, 'errs> {
             // Just check that
             self.type_checker.combine_domains::<false, _>(ref_domain, &DomainType::Physical(condition_domain.0), |wire_ref_domain_name, condition_domain_name| {
                 let wire_ref_domain_name = wire_ref_domain_name.unwrap();
-                self.errors.error(span, format!("Attempting to write to a wire from domain '{wire_ref_domain_name}' within a condition in domain '{condition_domain_name}'"))
+                self.errors.error(DiagnosticCode::DomainMismatch, span, format!("Attempting to write to a wire from domain '{wire_ref_domain_name}' within a condition in domain '{condition_domain_name}'"))
                     .info_same_file(condition_domain.1, format!("This condition has domain '{condition_domain_name}'"));
             });
         }
@@ -385,12 +391,25 @@ impl<'l, 'errs> TypeCheckingContext<'l, 'errs> {
                 self.typecheck_written_type(&decl.typ_expr);
 
                 let typ = decl.typ_expr.to_type();
+                let cross_domain = match decl.is_port {
+                    DeclarationPortInfo::Crossed { from, group_leader } => {
+                        let from_domain = self.working_on.instructions[from].unwrap_wire_declaration().typ.domain;
+                        let group_domain = (group_leader != instr_id).then(|| {
+                            self.working_on.instructions[group_leader].unwrap_wire_declaration().typ.domain
+                        });
+                        Some(self.type_checker.cross_domain(instr_id, &from_domain, group_domain.as_ref()))
+                    }
+                    _ => None,
+                };
                 let Instruction::Declaration(decl) =
                     &mut self.modules.working_on.instructions[instr_id]
                 else {
                     unreachable!()
                 };
                 decl.typ.typ = typ;
+                if let Some(cross_domain) = cross_domain {
+                    decl.typ.domain = cross_domain;
+                }
                 if decl.typ.domain == DomainType::Physical(DomainID::PLACEHOLDER) {
                     decl.typ.domain = self
                         .type_checker
@@ -451,6 +470,28 @@ impl<'l, 'errs> TypeCheckingContext<'l, 'errs> {
                         )
                     }
                     WireSource::Constant(value) => value.get_type_of_constant(),
+                    WireSource::ArrayLiteral(elements) => {
+                        let instructions = &self.working_on.instructions;
+                        self.type_checker.typecheck_array_literal(elements.iter().map(|elem| {
+                            let elem_wire = instructions[*elem].unwrap_wire();
+                            (&elem_wire.typ, elem_wire.span)
+                        }))
+                    }
+                    &WireSource::ArrayRepeat { element, count } => {
+                        let element_wire = self.working_on.instructions[element].unwrap_wire();
+                        let count_wire = self.working_on.instructions[count].unwrap_wire();
+                        self.type_checker.typecheck_and_generative::<true>(
+                            &count_wire.typ,
+                            count_wire.span,
+                            &INT_TYPE,
+                            "array repeat count",
+                            None,
+                        );
+                        FullType {
+                            typ: AbstractType::Array(Box::new(element_wire.typ.typ.clone())),
+                            domain: element_wire.typ.domain,
+                        }
+                    }
                 };
                 let Instruction::Wire(w) = &mut self.working_on.instructions[instr_id] else {
                     unreachable!()
@@ -556,6 +597,17 @@ impl<'l, 'errs> TypeCheckingContext<'l, 'errs> {
             }
         }
 
+        // Crossing only makes sense if the wire actually ends up in another domain
+        for crossed_decl in self.type_checker.redundant_crossings() {
+            let decl = self.working_on.instructions[crossed_decl].unwrap_wire_declaration();
+            let DeclarationPortInfo::Crossed { from, group_leader: _ } = decl.is_port else {
+                unreachable!()
+            };
+            self.errors
+                .warn(DiagnosticCode::RedundantCross, decl.name_span, "This wire is crossed into the domain it was already in")
+                .info_obj_same_file(self.working_on.instructions[from].unwrap_wire_declaration());
+        }
+
         self.modules.working_on.domains =
             self.type_checker
                 .final_domains
@@ -608,7 +660,7 @@ impl<'l, 'errs> TypeCheckingContext<'l, 'errs> {
         for (id, inst) in self.working_on.instructions.iter() {
             if !is_instance_used_map[id] {
                 if let Instruction::Declaration(decl) = inst {
-                    self.errors.warn(decl.name_span, "Unused Variable: This variable does not affect the output ports of this module");
+                    self.errors.warn(DiagnosticCode::UnusedVariable, decl.name_span, "Unused Variable: This variable does not affect the output ports of this module");
                 }
             }
         }
@@ -643,6 +695,9 @@ impl<'l, 'errs> TypeCheckingContext<'l, 'errs> {
                 }
                 Instruction::Declaration(decl) => {
                     decl.typ_expr.for_each_generative_input(&mut collector_func);
+                    if let DeclarationPortInfo::Crossed { from, group_leader: _ } = decl.is_port {
+                        collector_func(from);
+                    }
                 }
                 Instruction::Wire(wire) => {
                     wire.source.for_each_dependency(collector_func);
@@ -665,3 +720,65 @@ impl<'l, 'errs> TypeCheckingContext<'l, 'errs> {
         instruction_fanins
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler_top::test_util::{compile, error_messages};
+
+    fn errors_of(text: &str) -> Vec<String> {
+        let (linker, file_id) = compile(text);
+        error_messages(&linker, file_id)
+    }
+
+    #[test]
+    fn array_literal_elements_have_one_type() {
+        let errors = errors_of(
+            "module M {
+                output int[2] o
+                o = [1, true]
+            }",
+        );
+        assert_eq!(errors, ["Typing Error: array literal element expects a int but was given a bool"]);
+    }
+
+    #[test]
+    fn array_literal_type_is_an_array_of_its_elements() {
+        let errors = errors_of(
+            "module M {
+                interface M : bool a, bool b -> bool[2] pair, int[3] repeated
+                pair = [a, b]
+                repeated = [5; 3]
+            }",
+        );
+        assert_eq!(errors, Vec::<String>::new());
+
+        let errors = errors_of(
+            "module M {
+                interface M : bool a -> int[2] o
+                o = [a, a]
+            }",
+        );
+        assert_eq!(errors.len(), 1, "{errors:?}");
+    }
+
+    #[test]
+    fn empty_array_literal_is_rejected() {
+        let errors = errors_of(
+            "module M {
+                gen int[0] EMPTY = []
+            }",
+        );
+        assert_eq!(errors, ["Empty array literals are not allowed, because their type cannot be inferred"]);
+    }
+
+    #[test]
+    fn array_repeat_count_is_generative() {
+        let errors = errors_of(
+            "module M {
+                interface M : int count -> int[3] o
+                o = [1; count]
+            }",
+        );
+        assert!(errors.iter().any(|err| err.contains("array repeat count")), "{errors:?}");
+    }
+}
@@ -31,6 +31,15 @@ impl WireSource {
                 func(right)
             }
             WireSource::Constant(_) => {}
+            WireSource::ArrayLiteral(elements) => {
+                for elem in elements {
+                    func(*elem)
+                }
+            }
+            &WireSource::ArrayRepeat { element, count } => {
+                func(element);
+                func(count)
+            }
         }
     }
 }
@@ -407,6 +407,33 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
                 compute_binary_op(left_val, op, right_val)
             }
             WireSource::Constant(value) => TypedValue::from_value(value.clone()),
+            WireSource::ArrayLiteral(elements) => {
+                let first = self.generation_state.get_generation_value(elements[0])?;
+                let elem_typ = first.typ.clone();
+                let mut values = Vec::with_capacity(elements.len());
+                for elem in elements {
+                    let elem_val = self.generation_state.get_generation_value(*elem)?;
+                    if elem_val.typ != elem_typ {
+                        return Err((
+                            self.generation_state.span_of(*elem),
+                            format!(
+                                "Array literal elements must all be of the same type. The first element is a {}, but this is a {}",
+                                elem_typ.to_string(&self.linker.types),
+                                elem_val.typ.to_string(&self.linker.types)
+                            ),
+                        ));
+                    }
+                    values.push(elem_val.value.clone());
+                }
+                TypedValue::make_array(elem_typ, values)
+            }
+            &WireSource::ArrayRepeat { element, count } => {
+                let count = self
+                    .generation_state
+                    .get_generation_small_int::<usize>(count)?;
+                let element_val = self.generation_state.get_generation_value(element)?;
+                TypedValue::make_array(element_val.typ.clone(), vec![element_val.value.clone(); count])
+            }
         })
     }
     fn alloc_wire_for_const(
@@ -551,6 +578,25 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
             WireSource::Constant(_) => {
                 unreachable!("Constant cannot be non-compile-time");
             }
+            WireSource::ArrayLiteral(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|elem| self.get_wire_or_constant_as_wire(*elem, domain))
+                    .collect();
+                RealWireDataSource::ArrayLiteral { elements }
+            }
+            &WireSource::ArrayRepeat { element, count } => {
+                let count = self
+                    .generation_state
+                    .get_generation_small_int::<usize>(count)?;
+                if count == 0 {
+                    return Err((w.span, "Cannot repeat a non-generative value 0 times, because the resulting array would be empty".to_owned()));
+                }
+                let element = self.get_wire_or_constant_as_wire(element, domain);
+                RealWireDataSource::ArrayLiteral {
+                    elements: vec![element; count],
+                }
+            }
         };
         Ok(self.wires.alloc(RealWire {
             name : self.unique_name_producer.get_unique_name(""),
@@ -583,6 +629,32 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
                 typ.get_initial_typed_val()
             };
             SubModuleOrWire::CompileTimeValue(value)
+        } else if let DeclarationPortInfo::Crossed { from, group_leader } = wire_decl.is_port {
+            // The input side stays in the original domain, such that the crossing point gets its own latency
+            let original_wire = self.generation_state[from].unwrap_wire();
+            let crossing_input = self.wires.alloc(RealWire {
+                name: self.unique_name_producer.get_unique_name(&format!("{}_cross", wire_decl.name)),
+                typ: typ.clone(),
+                original_instruction,
+                domain: self.wires[original_wire].domain,
+                source: RealWireDataSource::Select { root: original_wire, path: Vec::new() },
+                absolute_latency: CALCULATE_LATENCY_LATER,
+            });
+            let wire_id = self.wires.get_next_alloc_id();
+            let group_leader = if group_leader == original_instruction {
+                wire_id
+            } else {
+                self.generation_state[group_leader].unwrap_wire()
+            };
+            self.wires.alloc(RealWire {
+                name: self.unique_name_producer.get_unique_name(&wire_decl.name),
+                typ,
+                original_instruction,
+                domain: wire_decl.typ.domain.unwrap_physical(),
+                source: RealWireDataSource::CrossDomain { from: crossing_input, group_leader },
+                absolute_latency: CALCULATE_LATENCY_LATER,
+            });
+            SubModuleOrWire::Wire(wire_id)
         } else {
             let source = if wire_decl.read_only {
                 RealWireDataSource::ReadOnly
@@ -808,3 +880,35 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler_top::test_util::{compile, error_messages, verilog_of};
+
+    #[test]
+    fn generative_array_literals() {
+        let code = verilog_of(
+            "module Sum {
+                output int total
+                gen int[3] VALUES = [4, 5, 6]
+                gen int[2] REPEATED = [7; 2]
+                gen int TOTAL = VALUES[0] + VALUES[2] + REPEATED[1]
+                total = TOTAL
+            }",
+            "Sum",
+        );
+        assert!(code.contains("17"), "{code}");
+    }
+
+    #[test]
+    fn generative_array_literal_elements_have_the_same_size() {
+        let (linker, file_id) = compile(
+            "module M {
+                gen int[2][2] TABLE = [[1, 2], [3, 4, 5]]
+            }",
+        );
+        let errors = error_messages(&linker, file_id);
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert!(errors[0].starts_with("Array literal elements must all be of the same type"), "{errors:?}");
+    }
+}
@@ -1,5 +1,3 @@
-use crate::config::config;
-
 use super::list_of_lists::ListOfLists;
 
 #[derive(Debug, Clone, Copy)]
@@ -316,10 +314,6 @@ pub fn solve_latencies(
     outputs: &[usize],
     mut specified_latencies: Vec<SpecifiedLatency>,
 ) -> Result<Vec<i64>, LatencyCountingError> {
-    if config().debug_print_latency_graph {
-        print_latency_test_case(fanins, inputs, outputs, &specified_latencies);
-    }
-
     if fanins.len() == 0 {
         return Ok(Vec::new());
     }
@@ -439,36 +433,37 @@ pub fn solve_latencies(
         .collect())
 }
 
-fn print_latency_test_case(
+/// Prints the inputs of [solve_latencies] as a test case for this file
+pub fn print_latency_test_case(
     fanins: &ListOfLists<FanInOut>,
     inputs: &[usize],
     outputs: &[usize],
     specified_latencies: &[SpecifiedLatency],
 ) {
-    println!("==== BEGIN LATENCY TEST CASE ====");
-    println!("#[test]");
-    println!("fn new_test_case() {{");
-    println!("    let fanins : [&[FanInOut]; {}] = [", fanins.len());
+    eprintln!("==== BEGIN LATENCY TEST CASE ====");
+    eprintln!("#[test]");
+    eprintln!("fn new_test_case() {{");
+    eprintln!("    let fanins : [&[FanInOut]; {}] = [", fanins.len());
     for (idx, fin) in fanins.iter().enumerate() {
-        print!("        /*{idx}*/&[");
+        eprint!("        /*{idx}*/&[");
         for FanInOut {
             other,
             delta_latency,
         } in fin
         {
-            print!("mk_fan({other}, {delta_latency}),")
+            eprint!("mk_fan({other}, {delta_latency}),")
         }
-        println!("],");
+        eprintln!("],");
     }
-    println!("    ];");
-    println!("    let fanins = ListOfLists::from_slice_slice(&fanins);");
-    println!("    let fanouts = convert_fanin_to_fanout(&fanins);");
-    println!("    let inputs = vec!{inputs:?};");
-    println!("    let outputs = vec!{outputs:?};");
-    println!("    let specified_latencies = vec!{specified_latencies:?};");
-    println!("    let found_latencies = solve_latencies(&fanins, &fanouts, &inputs, &outputs, specified_latencies).unwrap();");
-    println!("}}");
-    println!("==== END LATENCY TEST CASE ====");
+    eprintln!("    ];");
+    eprintln!("    let fanins = ListOfLists::from_slice_slice(&fanins);");
+    eprintln!("    let fanouts = convert_fanin_to_fanout(&fanins);");
+    eprintln!("    let inputs = vec!{inputs:?};");
+    eprintln!("    let outputs = vec!{outputs:?};");
+    eprintln!("    let specified_latencies = vec!{specified_latencies:?};");
+    eprintln!("    let found_latencies = solve_latencies(&fanins, &fanouts, &inputs, &outputs, specified_latencies).unwrap();");
+    eprintln!("}}");
+    eprintln!("==== END LATENCY TEST CASE ====");
 }
 
 #[cfg(test)]
@@ -5,8 +5,9 @@ use crate::prelude::*;
 use crate::{
     flattening::{Instruction, WriteModifiers},
     instantiation::latency_algorithm::{
-        convert_fanin_to_fanout, solve_latencies, FanInOut, LatencyCountingError,
+        convert_fanin_to_fanout, print_latency_test_case, solve_latencies, FanInOut, LatencyCountingError,
     },
+    logging::{log_enabled, LogLevel, LogSubsystem},
 };
 
 use self::list_of_lists::ListOfLists;
@@ -126,6 +127,13 @@ impl RealWireDataSource {
                 RealWirePathElem::for_each_wire_in_path(path, |w| f(w, 0));
             }
             RealWireDataSource::Constant { value: _ } => {}
+            RealWireDataSource::ArrayLiteral { elements } => {
+                for elem in elements {
+                    f(*elem, 0);
+                }
+            }
+            // Crosses domains, so no latency relation with the source. See [InstantiationContext::chain_cross_group]
+            RealWireDataSource::CrossDomain { from: _, group_leader: _ } => {}
         }
     }
 }
@@ -245,6 +253,33 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
             }
         }
 
+        let mut cross_groups: FlatAlloc<Vec<WireID>, WireIDMarker> = self.wires.map(|_| Vec::new());
+        for (w_id, w) in &self.wires {
+            if let RealWireDataSource::CrossDomain { from: _, group_leader } = w.source {
+                cross_groups[group_leader].push(w_id);
+            }
+        }
+        for (_leader, group) in &cross_groups {
+            if group.len() <= 1 {
+                continue;
+            }
+            self.chain_cross_group(&mut next_port_chain, group);
+            // Crossed wires may come from different domains. Only those sharing a domain can be kept aligned
+            for domain_id in self.md.domains.id_range() {
+                let inputs: Vec<WireID> = group
+                    .iter()
+                    .map(|w| {
+                        let RealWireDataSource::CrossDomain { from, group_leader: _ } = self.wires[*w].source else {
+                            unreachable!()
+                        };
+                        from
+                    })
+                    .filter(|from| self.wires[*from].domain == domain_id)
+                    .collect();
+                self.chain_cross_group(&mut next_port_chain, &inputs);
+            }
+        }
+
         // Every wire has been covered
         debug_assert!(map_wire_to_latency_node
             .iter()
@@ -257,6 +292,28 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
         }
     }
 
+    /// Ties the wires of one `cross` statement together in a cycle of zero latency edges, so they all get the same latency
+    fn chain_cross_group(
+        &self,
+        next_port_chain: &mut FlatAlloc<Option<(WireID, i64)>, WireIDMarker>,
+        group: &[WireID],
+    ) {
+        if group.len() <= 1 {
+            return;
+        }
+        let mut prev = *group.last().unwrap();
+        for w in group {
+            let port_ref = &mut next_port_chain[*w];
+            if port_ref.is_some() {
+                let span = self.md.get_instruction_span(self.wires[*w].original_instruction);
+                self.errors.error(DiagnosticCode::Latency, span, "This wire's latency is already tied to another wire, so it can't also be kept aligned with the other crossed wires");
+                continue;
+            }
+            *port_ref = Some((prev, 0));
+            prev = *w;
+        }
+    }
+
     fn make_fanins(
         &self,
         latency_node_mapper: &WireToLatencyMap,
@@ -308,7 +365,7 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
                 if sources.is_empty() && port_wire.absolute_latency == CALCULATE_LATENCY_LATER {
                     any_invalid_port = true;
                     let port = &self.md.ports[port_id];
-                    self.errors.error(port.name_span, format!("Pre-emptive error because latency-unspecified '{}' is never written to. \n(This is because work-in-progress code would get a lot of latency counting errors while unfinished)", port.name));
+                    self.errors.error(DiagnosticCode::Latency, port.name_span, format!("Pre-emptive error because latency-unspecified '{}' is never written to. \n(This is because work-in-progress code would get a lot of latency counting errors while unfinished)", port.name));
                 }
             }
         }
@@ -328,6 +385,15 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
             // Process fanouts
             let fanouts = convert_fanin_to_fanout(&fanins);
 
+            if log_enabled(&self.linker.config, LogLevel::Trace, LogSubsystem::Latency) {
+                print_latency_test_case(
+                    &fanins,
+                    &domain_info.input_ports,
+                    &domain_info.output_ports,
+                    &domain_info.initial_values,
+                );
+            }
+
             match solve_latencies(
                 &fanins,
                 &fanouts,
@@ -345,6 +411,7 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
                             let source_location =
                                 self.md.get_instruction_span(wire.original_instruction);
                             self.errors.error(
+                                DiagnosticCode::Latency,
                                 source_location,
                                 format!("Latency Counting couldn't reach this node"),
                             );
@@ -445,6 +512,7 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
                                     "These registers are"
                                 };
                                 self.errors.error(
+                                    DiagnosticCode::Latency,
                                     regs_span,
                                     format!("{this_register_plural}{rest_of_message}"),
                                 );
@@ -459,7 +527,7 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
                 if !did_place_error {
                     for wr in unique_write_instructions {
                         self.errors
-                            .error(wr.to_span, format!("This write is{rest_of_message}"));
+                            .error(DiagnosticCode::Latency, wr.to_span, format!("This write is{rest_of_message}"));
                     }
                 }
             }
@@ -468,7 +536,7 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
                     let port_decl = self.md.instructions
                         [self.wires[latency_node_meanings[port.0]].original_instruction]
                         .unwrap_wire_declaration();
-                    self.errors.error(port_decl.name_span, format!("Cannot determine port latency. Options are {} and {}\nTry specifying an explicit latency or rework the module to remove this ambiguity", port.1, port.2));
+                    self.errors.error(DiagnosticCode::Latency, port_decl.name_span, format!("Cannot determine port latency. Options are {} and {}\nTry specifying an explicit latency or rework the module to remove this ambiguity", port.1, port.2));
                 }
             }
             LatencyCountingError::ConflictingSpecifiedLatencies { conflict_path } => {
@@ -495,9 +563,43 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
                 let end_name = &end_wire.name;
                 let specified_end_latency = end_wire.absolute_latency;
                 self.errors
-                    .error(end_latency_decl.span, format!("Conflicting specified latency\n\n{path_message}\nBut this was specified as {end_name}'{specified_end_latency}"))
+                    .error(DiagnosticCode::Latency, end_latency_decl.span, format!("Conflicting specified latency\n\n{path_message}\nBut this was specified as {end_name}'{specified_end_latency}"))
                     .info_obj_same_file(start_decl);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler_top::test_util::instantiate;
+
+    #[test]
+    fn crossed_wires_keep_their_relative_latency() {
+        let (_linker, _md_id, instance) = instantiate(
+            "module CrossPair {
+                interface CrossPair : int a, int b
+                reg reg int a_late = a
+                int b_now = b
+                cross a_late, b_now
+                domain out
+                output int x
+                output int y
+                x = a_late
+                y = b_now
+            }",
+            "CrossPair",
+        );
+        let mut crossed = Vec::new();
+        for (_id, w) in &instance.wires {
+            if let RealWireDataSource::CrossDomain { from, group_leader: _ } = w.source {
+                crossed.push((w.absolute_latency, instance.wires[from].absolute_latency));
+            }
+        }
+        assert_eq!(crossed.len(), 2, "{:?}", instance.wires);
+        // b_now only crosses once it lines up with a_late
+        assert_eq!(crossed[0].1, crossed[1].1);
+        assert_eq!(crossed[0].0, crossed[1].0);
+    }
+}
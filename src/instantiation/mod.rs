@@ -13,8 +13,10 @@ use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use crate::flattening::{BinaryOperator, Module, UnaryOperator};
 use crate::{
-    config,
+    config::ResetConfig,
+    linker::IsExtern,
     errors::{CompileError, ErrorStore},
+    logging::{log_enabled, LogLevel, LogSubsystem},
     to_string::pretty_print_concrete_instance,
     value::{TypedValue, Value},
 };
@@ -83,6 +85,15 @@ pub enum RealWireDataSource {
     Constant {
         value: Value,
     },
+    ArrayLiteral {
+        elements: Vec<WireID>,
+    },
+    /// Output side of a `cross` statement. `from` is in another domain.
+    /// All wires with the same `group_leader` keep their relative latency
+    CrossDomain {
+        from: WireID,
+        group_leader: WireID,
+    },
 }
 
 #[derive(Debug)]
@@ -133,6 +144,9 @@ pub struct InstantiatedModule {
     pub wires: FlatAlloc<RealWire, WireIDMarker>,
     pub submodules: FlatAlloc<SubModule, SubModuleIDMarker>,
     pub generation_state: FlatAlloc<SubModuleOrWire, FlatIDMarker>,
+    /// Set when this module resets its own state, or when one of its submodules has a reset port, so it can pass the reset on.
+    /// Otherwise that submodule would be held out of reset, and its state never initialized
+    pub has_reset_port: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -207,13 +221,13 @@ impl InstantiationList {
 
             let result = perform_instantiation(md, linker, &template_args);
 
-            if config().debug_print_module_contents {
-                println!("[[Instantiated {}]]", result.name);
+            if log_enabled(&linker.config, LogLevel::Trace, LogSubsystem::Instantiation) {
+                eprintln!("[[Instantiated {}]]", result.name);
                 for (id, w) in &result.wires {
-                    println!("{id:?} -> {w:?}");
+                    eprintln!("{id:?} -> {w:?}");
                 }
                 for (id, sm) in &result.submodules {
-                    println!("SubModule {id:?}: {sm:?}");
+                    eprintln!("SubModule {id:?}: {sm:?}");
                 }
             }
 
@@ -233,11 +247,11 @@ impl InstantiationList {
         }
     }
 
-    pub fn for_each_error<F: FnMut(&CompileError)>(&self, func: &mut F) {
+    pub fn for_each_error<F: FnMut(&CompileError, Option<&str>)>(&self, func: &mut F) {
         let cache_borrow = self.cache.borrow();
         for inst in cache_borrow.values() {
             for err in &inst.errors {
-                func(err)
+                func(err, Some(&inst.name))
             }
         }
     }
@@ -287,6 +301,8 @@ struct InstantiationContext<'fl, 'l> {
     template_args: &'fl ConcreteTemplateArgs,
     md: &'fl Module,
     linker: &'l Linker,
+
+    has_reset_port: bool,
 }
 
 impl<'fl, 'l> InstantiationContext<'fl, 'l> {
@@ -298,6 +314,7 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
             interface_ports: self.interface_ports,
             generation_state: self.generation_state.generation_state,
             errors: self.errors.into_storage(),
+            has_reset_port: self.has_reset_port,
         }
     }
 
@@ -334,7 +351,7 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
                             // We have a second routine that reports invalid interfaces.
                             let source_code_port = &sub_module.ports[port_id];
                             for span in &connecting_wire.name_refs {
-                                self.errors.error(*span, format!("Port '{}' is used, but the instantiated module has this port disabled", source_code_port.name))
+                                self.errors.error(DiagnosticCode::Instantiation, *span, format!("Port '{}' is used, but the instantiated module has this port disabled", source_code_port.name))
                                     .info_obj_different_file(source_code_port, sub_module.link_info.file)
                                     .info_obj_same_file(submod_instr);
                             }
@@ -344,6 +361,7 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
                             let source_code_port = &sub_module.ports[port_id];
                             self.errors
                                 .warn(
+                                    DiagnosticCode::UnusedPort,
                                     submod_instr.module_ref.span,
                                     format!("Unused port '{}'", source_code_port.name),
                                 )
@@ -370,7 +388,7 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
                         {
                             if instance.interface_ports[representative_port].is_none() {
                                 for span in interface_references {
-                                    self.errors.error(*span, format!("The interface '{interface_name}' is disabled in this submodule instance"))
+                                    self.errors.error(DiagnosticCode::Instantiation, *span, format!("The interface '{interface_name}' is disabled in this submodule instance"))
                                         .info_obj_same_file(submod_instr)
                                         .info((sm_interface.name_span, sub_module.link_info.file), format!("Interface '{interface_name}' declared here"));
                                 }
@@ -396,6 +414,7 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
                 sm.instance = Some(instance);
             } else {
                 self.errors.error(
+                    DiagnosticCode::Instantiation,
                     submod_instr.module_ref.span,
                     "Error instantiating submodule",
                 );
@@ -404,6 +423,44 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
         }
         success
     }
+
+    /// Must be called after [Self::instantiate_submodules], so the submodules already know whether they have a reset port
+    fn compute_has_reset_port(&mut self) {
+        let own_reset = get_reset_for(self.linker, self.md).is_some();
+        let is_normal = matches!(self.md.link_info.is_extern, IsExtern::Normal);
+        let submodule_reset = self.submodules.iter().any(|(_, sm)| sm.instance.as_ref().is_some_and(|inst| inst.has_reset_port));
+        self.has_reset_port = own_reset || (is_normal && submodule_reset);
+    }
+
+    /// The reset port is added by code generation, so it must not share its name with a wire or submodule
+    fn check_reset_port_name(&mut self) {
+        let (true, Some(reset)) = (self.has_reset_port, &self.linker.config.reset) else {
+            return;
+        };
+        let rst = reset.port_name();
+        let colliding: Vec<FlatID> = self
+            .wires
+            .iter()
+            .filter(|(_, w)| w.name == rst)
+            .map(|(_, w)| w.original_instruction)
+            .chain(self.submodules.iter().filter(|(_, sm)| sm.name == rst).map(|(_, sm)| sm.original_instruction))
+            .collect();
+        for original_instruction in colliding {
+            self.errors.error(
+                DiagnosticCode::NameCollision,
+                self.md.get_instruction_span(original_instruction),
+                format!("'{rst}' is the name of the reset port that is added to this module. Rename this"),
+            );
+        }
+    }
+}
+
+/// The reset of this module's own state. Only regular modules get a reset. Extern modules have their signatures provided externally, and builtins don't have state
+pub fn get_reset_for<'l>(linker: &'l Linker, md: &Module) -> Option<&'l ResetConfig> {
+    let reset = linker.config.reset.as_ref()?;
+    let is_normal = matches!(md.link_info.is_extern, IsExtern::Normal);
+    let has_reset_domain = md.domains.iter().any(|(_, domain)| reset.applies_to_domain(&domain.name));
+    (is_normal && reset.applies_to_module(&md.link_info.name) && has_reset_domain).then_some(reset)
 }
 
 fn perform_instantiation(
@@ -426,45 +483,45 @@ fn perform_instantiation(
         template_args,
         md,
         linker,
+        has_reset_port: false,
     };
 
     // Don't instantiate modules that already errored. Otherwise instantiator may crash
     if md.link_info.errors.did_error {
-        println!(
-            "Not Instantiating {} due to flattening errors",
-            md.link_info.name
-        );
+        log!(linker.config, Debug, Instantiation, "Not Instantiating {} due to flattening errors", md.link_info.name);
         context.errors.set_did_error();
         return context.extract();
     }
 
-    println!("Instantiating {}", md.link_info.name);
+    log!(linker.config, Debug, Instantiation, "Instantiating {}", md.link_info.name);
 
     if let Err(e) = context.execute_module() {
-        context.errors.error(e.0, e.1);
+        context.errors.error(DiagnosticCode::GenerativeExecution, e.0, e.1);
 
         return context.extract();
     }
 
-    if config().debug_print_module_contents {
-        println!("[[Executed {}]]", &context.name);
+    if log_enabled(&linker.config, LogLevel::Trace, LogSubsystem::Instantiation) {
+        eprintln!("[[Executed {}]]", &context.name);
         for (id, w) in &context.wires {
-            println!("{id:?} -> {w:?}");
+            eprintln!("{id:?} -> {w:?}");
         }
         for (id, sm) in &context.submodules {
-            println!("SubModule {id:?}: {sm:?}");
+            eprintln!("SubModule {id:?}: {sm:?}");
         }
     }
 
-    println!("Instantiating submodules for {}", md.link_info.name);
+    log!(linker.config, Debug, Instantiation, "Instantiating submodules for {}", md.link_info.name);
     if !context.instantiate_submodules() {
         return context.extract();
     }
+    context.compute_has_reset_port();
+    context.check_reset_port_name();
 
-    println!("Concrete Typechecking {}", md.link_info.name);
+    log!(linker.config, Debug, Instantiation, "Concrete Typechecking {}", md.link_info.name);
     context.typecheck();
 
-    println!("Latency Counting {}", md.link_info.name);
+    log!(linker.config, Debug, Latency, "Latency Counting {}", md.link_info.name);
     context.compute_latencies();
 
     context.extract()
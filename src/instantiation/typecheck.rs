@@ -75,6 +75,28 @@ impl<'fl, 'l> InstantiationContext<'fl, 'l> {
                         "Assigned type to a constant should already be of the type"
                     );
                 }
+                RealWireDataSource::ArrayLiteral { elements } => {
+                    let elem_typ = self.wires[elements[0]].typ.clone();
+                    for elem in &elements[1..] {
+                        elem_typ.check_type(&self.wires[*elem].typ, span, &self.linker.types, &self.errors);
+                    }
+                    let found_typ = ConcreteType::new_array(elem_typ, elements.len());
+                    self.wires[this_wire_id].typ.check_or_update_type(
+                        &found_typ,
+                        span,
+                        &self.linker.types,
+                        &self.errors,
+                    );
+                }
+                &RealWireDataSource::CrossDomain { from, group_leader: _ } => {
+                    let found_typ = self.wires[from].typ.clone();
+                    self.wires[this_wire_id].typ.check_or_update_type(
+                        &found_typ,
+                        span,
+                        &self.linker.types,
+                        &self.errors,
+                    );
+                }
             };
         }
 
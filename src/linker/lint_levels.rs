@@ -0,0 +1,235 @@
+//! Decides whether a warning is allowed, reported as a warning, or denied and reported as an error.
+//!
+//! From most to least specific: `#[allow(code)]`, `#[warn(code)]` and `#[deny(code)]` annotations in the comments above a
+//! module, struct, declaration or submodule, then the `-A`/`-W`/`-D` flags, then `--deny-warnings` or the project's `warnings` policy.
+//!
+//! The grammar has no attribute syntax, so the annotations are written in comments:
+//!
+//! ```sus
+//! // #[allow(unused_variable, W0003)]
+//! int debug_counter
+//! ```
+//!
+//! Levels and codes that don't exist are reported as [DiagnosticCode::UnknownLint] warnings, so typos don't go unnoticed.
+
+use std::ops::Range;
+
+use crate::prelude::*;
+
+use crate::config::{ConfigStruct, WarningPolicy};
+use crate::errors::{CompileError, DiagnosticCode, ErrorLevel};
+use crate::file_position::FileText;
+use crate::flattening::Instruction;
+
+use super::{Documentation, NameElem};
+
+/// An annotation, applying to every warning within `span`
+pub struct LintScope {
+    span: Span,
+    code: DiagnosticCode,
+    level: WarningPolicy,
+}
+
+#[derive(Default)]
+pub struct LintAnnotations {
+    pub scopes: Vec<LintScope>,
+    /// Annotations naming unknown levels or codes
+    pub warnings: Vec<CompileError>,
+}
+
+impl LintAnnotations {
+    fn warn(&mut self, position: Span, reason: String) {
+        self.warnings.push(CompileError {
+            code: DiagnosticCode::UnknownLint,
+            position,
+            reason,
+            infos: Vec::new(),
+            suggestions: Vec::new(),
+            level: ErrorLevel::Warning,
+        });
+    }
+
+    /// Finds all `#[allow(a, b)]`, `#[warn(...)]` and `#[deny(...)]` in the comments
+    fn parse(&mut self, documentation: &Documentation, file_text: &FileText, span: Span) {
+        for comment in documentation.gathered.iter() {
+            let comment_start = comment.into_range().start;
+            let comment_text = &file_text[*comment];
+            let span_in_comment = |range: Range<usize>| Span::from(comment_start + range.start..comment_start + range.end);
+
+            let mut searched_up_to = 0;
+            while let Some(found) = comment_text[searched_up_to..].find("#[") {
+                let attribute_start = searched_up_to + found + 2;
+                let Some(attribute_len) = comment_text[attribute_start..].find(']') else {
+                    break;
+                };
+                let attribute_end = attribute_start + attribute_len;
+                searched_up_to = attribute_end + 1;
+
+                let attribute = &comment_text[attribute_start..attribute_end];
+                let Some((level_text, codes)) = attribute.split_once('(') else {
+                    continue;
+                };
+                let level_name = level_text.trim();
+                let level = match level_name {
+                    "allow" => WarningPolicy::Allow,
+                    "warn" => WarningPolicy::Warn,
+                    "deny" => WarningPolicy::Deny,
+                    // Other text in comments can contain '#[' too, only complain about what looks like an annotation
+                    _ if !level_name.is_empty() && level_name.chars().all(|c| c.is_alphanumeric() || c == '_') => {
+                        self.warn(
+                            span_in_comment(attribute_start..attribute_start + level_text.len()),
+                            format!("Unknown lint level '{level_name}'. Expected allow, warn or deny"),
+                        );
+                        continue;
+                    }
+                    _ => continue,
+                };
+
+                let mut code_start = attribute_start + level_text.len() + 1;
+                for code_text in codes.trim_end().trim_end_matches(')').split(',') {
+                    let code_range = code_start..code_start + code_text.len();
+                    code_start = code_range.end + 1;
+                    let code_name = code_text.trim();
+                    if code_name.is_empty() {
+                        continue;
+                    }
+                    match DiagnosticCode::from_code_or_name(code_name) {
+                        Some(code) if code.is_warning() => self.scopes.push(LintScope { span, code, level }),
+                        Some(code) => self.warn(
+                            span_in_comment(code_range),
+                            format!("'{code_name}' ({}) is an error. Only warnings can be allowed, warned or denied", code.name()),
+                        ),
+                        None => self.warn(span_in_comment(code_range), format!("Unknown diagnostic code '{code_name}'")),
+                    }
+                }
+            }
+        }
+    }
+
+    fn parse_instructions(&mut self, instructions: &FlatAlloc<Instruction, FlatIDMarker>, file_text: &FileText) {
+        for (_id, instr) in instructions {
+            match instr {
+                Instruction::Declaration(decl) => self.parse(&decl.documentation, file_text, decl.decl_span),
+                Instruction::SubModule(sm) => {
+                    let span = match &sm.name {
+                        Some((_name, name_span)) => Span::new_overarching(sm.module_ref.span, *name_span),
+                        None => sm.module_ref.span,
+                    };
+                    self.parse(&sm.documentation, file_text, span)
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+pub fn gather_lint_annotations(linker: &Linker, file: FileUUID) -> LintAnnotations {
+    let file_data = &linker.files[file];
+    let file_text = &file_data.file_text;
+    let mut annotations = LintAnnotations::default();
+    for global in &file_data.associated_values {
+        match global {
+            NameElem::Module(md_id) => {
+                let md = &linker.modules[*md_id];
+                annotations.parse(&md.link_info.documentation, file_text, md.link_info.span);
+                annotations.parse_instructions(&md.instructions, file_text);
+            }
+            NameElem::Type(typ_id) => {
+                let typ = &linker.types[*typ_id];
+                annotations.parse(&typ.link_info.documentation, file_text, typ.link_info.span);
+                annotations.parse_instructions(&typ.instructions, file_text);
+            }
+            NameElem::Constant(_) => {}
+        }
+    }
+    annotations
+}
+
+/// Errors always stay errors. Only warnings get a level
+pub fn lint_level(config: &ConfigStruct, scopes: &[LintScope], err: &CompileError) -> WarningPolicy {
+    let innermost_annotation = scopes
+        .iter()
+        .filter(|scope| scope.code == err.code && scope.span.contains_pos(err.position.into_range().start))
+        .min_by_key(|scope| scope.span.size());
+    if let Some(scope) = innermost_annotation {
+        return scope.level;
+    }
+
+    // Later flags override earlier ones
+    if let Some((_code, level)) = config.lint_levels.iter().rev().find(|(code, _)| *code == err.code) {
+        return *level;
+    }
+    config.warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses the annotations in `comment`, applying to the text after it
+    fn parse_comment(comment: &str, annotated: &str) -> (LintAnnotations, FileText, Span) {
+        let file_text = FileText::new(format!("{comment}\n{annotated}"));
+        let documentation = Documentation { gathered: Box::new([Span::from(0..comment.len())]) };
+        let annotated_span = Span::from(comment.len() + 1..file_text.len());
+        let mut annotations = LintAnnotations::default();
+        annotations.parse(&documentation, &file_text, annotated_span);
+        (annotations, file_text, annotated_span)
+    }
+
+    fn warning_at(code: DiagnosticCode, position: usize) -> CompileError {
+        CompileError {
+            code,
+            position: Span::from(position..position + 1),
+            reason: String::new(),
+            infos: Vec::new(),
+            suggestions: Vec::new(),
+            level: ErrorLevel::Warning,
+        }
+    }
+
+    #[test]
+    fn annotations_apply_to_the_annotated_span() {
+        let (annotations, _file_text, annotated_span) = parse_comment("// #[allow(unused_variable, W0003)] #[deny( W0002 )]", "int x");
+        assert!(annotations.warnings.is_empty());
+        let scopes: Vec<(Span, DiagnosticCode, WarningPolicy)> = annotations.scopes.iter().map(|scope| (scope.span, scope.code, scope.level)).collect();
+        assert_eq!(
+            scopes,
+            vec![
+                (annotated_span, DiagnosticCode::UnusedVariable, WarningPolicy::Allow),
+                (annotated_span, DiagnosticCode::UnusedResult, WarningPolicy::Allow),
+                (annotated_span, DiagnosticCode::UnusedPort, WarningPolicy::Deny),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_levels_and_codes_are_reported() {
+        let (annotations, file_text, _annotated_span) =
+            parse_comment("// #[deny(W9999, type_error)] #[frobnicate(unused_port)] see #[1]", "int x");
+        assert!(annotations.scopes.is_empty());
+        let warnings: Vec<(&str, &str)> = annotations.warnings.iter().map(|warning| (&file_text[warning.position], warning.reason.as_str())).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                ("W9999", "Unknown diagnostic code 'W9999'"),
+                (" type_error", "'type_error' (type_error) is an error. Only warnings can be allowed, warned or denied"),
+                ("frobnicate", "Unknown lint level 'frobnicate'. Expected allow, warn or deny"),
+            ]
+        );
+        assert!(annotations.warnings.iter().all(|warning| warning.code == DiagnosticCode::UnknownLint));
+    }
+
+    #[test]
+    fn innermost_scope_wins() {
+        let config = ConfigStruct::default();
+        let scopes = [
+            LintScope { span: Span::from(0..100), code: DiagnosticCode::UnusedVariable, level: WarningPolicy::Allow },
+            LintScope { span: Span::from(20..30), code: DiagnosticCode::UnusedVariable, level: WarningPolicy::Deny },
+        ];
+        assert_eq!(lint_level(&config, &scopes, &warning_at(DiagnosticCode::UnusedVariable, 25)), WarningPolicy::Deny);
+        assert_eq!(lint_level(&config, &scopes, &warning_at(DiagnosticCode::UnusedVariable, 50)), WarningPolicy::Allow);
+        // Other codes and positions outside of all scopes fall back to the configuration
+        assert_eq!(lint_level(&config, &scopes, &warning_at(DiagnosticCode::UnusedPort, 25)), config.warnings);
+        assert_eq!(lint_level(&config, &scopes, &warning_at(DiagnosticCode::UnusedVariable, 150)), config.warnings);
+    }
+}
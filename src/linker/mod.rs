@@ -1,4 +1,5 @@
 pub mod checkpoint;
+mod lint_levels;
 mod resolver;
 pub use resolver::*;
 
@@ -13,6 +14,7 @@ use tree_sitter::Tree;
 
 use crate::{
     alloc::ArenaAllocator,
+    config::{ConfigStruct, WarningPolicy},
     file_position::FileText,
     flattening::Module,
     util::{const_str_position, const_str_position_in_tuples},
@@ -231,16 +233,19 @@ pub struct Linker {
     pub constants: ArenaAllocator<NamedConstant, ConstantUUIDMarker>,
     pub files: ArenaAllocator<FileData, FileUUIDMarker>,
     global_namespace: HashMap<String, NamespaceElement>,
+    /// The options of this compilation. Code generation and error reporting read them from here
+    pub config: ConfigStruct,
 }
 
 impl Linker {
-    pub fn new() -> Linker {
+    pub fn new(config: ConfigStruct) -> Linker {
         let mut result = Linker {
             types: ArenaAllocator::new(),
             modules: ArenaAllocator::new(),
             constants: ArenaAllocator::new(),
             files: ArenaAllocator::new(),
             global_namespace: HashMap::new(),
+            config,
         };
 
         fn add_known_unique_name(result: &mut Linker, name: String, new_obj_id: NameElem) {
@@ -350,16 +355,18 @@ impl Linker {
                     format!("'{this_object_name}' conflicts with other declarations:")
                 };
                 f(&CompileError {
+                    code: DiagnosticCode::NameCollision,
                     position: info.name_span,
                     reason,
                     infos,
+                    suggestions: Vec::new(),
                     level: ErrorLevel::Error,
                 });
             }
         }
     }
 
-    fn for_all_errors_after_compile<F: FnMut(&CompileError)>(
+    fn for_all_errors_after_compile<F: FnMut(&CompileError, Option<&str>)>(
         &self,
         file_uuid: FileUUID,
         func: &mut F,
@@ -369,7 +376,7 @@ impl Linker {
                 NameElem::Module(md_id) => {
                     let md = &self.modules[*md_id];
                     for e in &md.link_info.errors {
-                        func(e)
+                        func(e, None)
                     }
                     md.instantiations.for_each_error(func);
                 }
@@ -380,11 +387,39 @@ impl Linker {
     }
 
     pub fn for_all_errors_in_file<F: FnMut(&CompileError)>(&self, file_uuid: FileUUID, mut f: F) {
+        self.for_all_errors_in_file_with_instance(file_uuid, |err, _instance_name| f(err));
+    }
+
+    /// Like [Self::for_all_errors_in_file], but also passes the name of the instance that instantiation errors were found in.
+    ///
+    /// Allowed warnings are skipped, and denied warnings are passed as errors. See [lint_levels]
+    pub fn for_all_errors_in_file_with_instance<F: FnMut(&CompileError, Option<&str>)>(&self, file_uuid: FileUUID, mut f: F) {
+        let lint_annotations = lint_levels::gather_lint_annotations(self, file_uuid);
+        let lint_scopes = &lint_annotations.scopes;
+        let mut report = |err: &CompileError, instance_name: Option<&str>| {
+            if !err.code.is_warning() {
+                return f(err, instance_name);
+            }
+            match lint_levels::lint_level(&self.config, lint_scopes, err) {
+                WarningPolicy::Allow => {}
+                WarningPolicy::Warn => f(err, instance_name),
+                WarningPolicy::Deny => {
+                    let denied = CompileError {
+                        level: ErrorLevel::Error,
+                        ..err.clone()
+                    };
+                    f(&denied, instance_name)
+                }
+            }
+        };
         for err in &self.files[file_uuid].parsing_errors {
-            f(err);
+            report(err, None);
+        }
+        for err in &lint_annotations.warnings {
+            report(err, None);
         }
-        self.for_all_duplicate_declaration_errors(file_uuid, &mut f);
-        self.for_all_errors_after_compile(file_uuid, &mut f);
+        self.for_all_duplicate_declaration_errors(file_uuid, &mut |err| report(err, None));
+        self.for_all_errors_after_compile(file_uuid, &mut report);
     }
 
     pub fn remove_everything_in_file(&mut self, file_uuid: FileUUID) -> &mut FileData {
@@ -424,7 +459,6 @@ impl Linker {
         file_data
     }
 
-    #[allow(dead_code)]
     pub fn remove_file(&mut self, file_uuid: FileUUID) {
         self.remove_everything_in_file(file_uuid);
         self.files.free(file_uuid);
@@ -34,6 +34,13 @@ impl ResolvedGlobals {
     pub fn checkpoint(&self) -> ResolvedGlobalsCheckpoint {
         ResolvedGlobalsCheckpoint(self.referenced_globals.len(), self.all_resolved)
     }
+    pub fn iter(&self) -> std::slice::Iter<'_, NameElem> {
+        self.referenced_globals.iter()
+    }
+    /// If some name could not be resolved, then adding a global anywhere may change the result
+    pub fn all_resolved(&self) -> bool {
+        self.all_resolved
+    }
 }
 
 pub struct Resolver<'linker, 'err_and_globals, IDM: UUIDMarker, T> {
@@ -101,7 +108,7 @@ impl<'linker, 'err_and_globals> NameResolver<'linker, 'err_and_globals> {
             Some(NamespaceElement::Colission(coll)) => {
                 resolved_globals.all_resolved = false;
 
-                let err_ref = self.errors.error(name_span, format!("There were colliding imports for the name '{name}'. Pick one and import it by name."));
+                let err_ref = self.errors.error(DiagnosticCode::NameCollision, name_span, format!("There were colliding imports for the name '{name}'. Pick one and import it by name."));
 
                 for collider_global in coll.iter() {
                     let err_loc = linker.get_linking_error_location(*collider_global);
@@ -125,6 +132,7 @@ impl<'linker, 'err_and_globals> NameResolver<'linker, 'err_and_globals> {
                 resolved_globals.all_resolved = false;
 
                 self.errors.error(
+                    DiagnosticCode::UnknownName,
                     name_span,
                     format!(
                         "No Global of the name '{name}' was found. Did you forget to import it?"
@@ -145,6 +153,7 @@ impl<'linker, 'err_and_globals> NameResolver<'linker, 'err_and_globals> {
         let name = &info.full_name;
         let global_type = info.named_type;
         let err_ref = self.errors.error(
+            DiagnosticCode::WrongKindOfName,
             span,
             format!("{name} is not a {expected}, it is a {global_type} instead!"),
         );
@@ -0,0 +1,193 @@
+//! Progress and debug messages of the compiler itself, as opposed to the errors and warnings in the compiled code.
+//!
+//! Everything goes to stderr, so stdout stays free for `--message-format json` and the LSP. `-v` and `-q` set the level,
+//! `--log <subsystem>=<level>` overrides it for one subsystem.
+//!
+//! The levels are part of the [ConfigStruct], so every [Linker](crate::linker::Linker) logs according to its own config
+
+use crate::config::ConfigStruct;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    /// Dumps of the compiler's internal data structures
+    Trace,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 5] = [LogLevel::Error, LogLevel::Warn, LogLevel::Info, LogLevel::Debug, LogLevel::Trace];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|level| level.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogSubsystem {
+    /// Loading files and recompiling
+    Linker,
+    Flatten,
+    Instantiation,
+    Latency,
+    Codegen,
+    Lsp,
+}
+
+impl LogSubsystem {
+    pub const ALL: [LogSubsystem; 6] = [
+        LogSubsystem::Linker,
+        LogSubsystem::Flatten,
+        LogSubsystem::Instantiation,
+        LogSubsystem::Latency,
+        LogSubsystem::Codegen,
+        LogSubsystem::Lsp,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LogSubsystem::Linker => "linker",
+            LogSubsystem::Flatten => "flatten",
+            LogSubsystem::Instantiation => "instantiation",
+            LogSubsystem::Latency => "latency",
+            LogSubsystem::Codegen => "codegen",
+            LogSubsystem::Lsp => "lsp",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|subsystem| subsystem.name() == name)
+    }
+}
+
+/// Parses the argument of `--log`: `subsystem` or `subsystem=level`. Without a level, everything is logged
+pub fn parse_log_filter(filter: &str) -> Result<(LogSubsystem, LogLevel), String> {
+    let (subsystem, level) = match filter.split_once('=') {
+        Some((subsystem, level)) => (subsystem, Some(level)),
+        None => (filter, None),
+    };
+    let subsystem = LogSubsystem::from_name(subsystem.trim()).ok_or_else(|| {
+        let names: Vec<&str> = LogSubsystem::ALL.iter().map(|subsystem| subsystem.name()).collect();
+        format!("Unknown subsystem '{subsystem}', expected one of {}", names.join(", "))
+    })?;
+    let level = match level {
+        Some(level) => LogLevel::from_name(level.trim())
+            .ok_or_else(|| format!("Unknown log level '{level}', expected error, warn, info, debug or trace"))?,
+        None => LogLevel::Trace,
+    };
+    Ok((subsystem, level))
+}
+
+#[derive(Clone, Debug)]
+pub struct LogLevels {
+    pub level: LogLevel,
+    /// Overrides [Self::level] for a subsystem. Later ones override earlier ones
+    pub filters: Vec<(LogSubsystem, LogLevel)>,
+}
+
+impl Default for LogLevels {
+    fn default() -> Self {
+        Self {
+            level: LogLevel::Info,
+            filters: Vec::new(),
+        }
+    }
+}
+
+impl LogLevels {
+    pub fn enabled(&self, level: LogLevel, subsystem: LogSubsystem) -> bool {
+        let max_level = self
+            .filters
+            .iter()
+            .rev()
+            .find(|(filtered, _)| *filtered == subsystem)
+            .map_or(self.level, |(_, level)| *level);
+        level <= max_level
+    }
+}
+
+pub fn log_enabled(config: &ConfigStruct, level: LogLevel, subsystem: LogSubsystem) -> bool {
+    config.log_levels.enabled(level, subsystem)
+}
+
+pub fn write_log(level: LogLevel, subsystem: LogSubsystem, message: std::fmt::Arguments) {
+    eprintln!("[{} {}] {message}", level.name(), subsystem.name());
+}
+
+/// `log!(linker.config, Debug, Instantiation, "Instantiating {name}")`. The message is only formatted if it is logged
+macro_rules! log {
+    ($config:expr, $level:ident, $subsystem:ident, $($arg:tt)*) => {
+        if $crate::logging::log_enabled(&$config, $crate::logging::LogLevel::$level, $crate::logging::LogSubsystem::$subsystem) {
+            $crate::logging::write_log(
+                $crate::logging::LogLevel::$level,
+                $crate::logging::LogSubsystem::$subsystem,
+                format_args!($($arg)*),
+            );
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WarningPolicy;
+    use crate::linker::Linker;
+
+    #[test]
+    fn parse_filters() {
+        assert_eq!(parse_log_filter("latency"), Ok((LogSubsystem::Latency, LogLevel::Trace)));
+        assert_eq!(parse_log_filter("lsp = warn"), Ok((LogSubsystem::Lsp, LogLevel::Warn)));
+        assert!(parse_log_filter("typing=debug").is_err());
+        assert!(parse_log_filter("lsp=loud").is_err());
+    }
+
+    #[test]
+    fn later_filters_override_earlier_ones() {
+        let log_levels = LogLevels {
+            level: LogLevel::Warn,
+            filters: vec![(LogSubsystem::Codegen, LogLevel::Trace), (LogSubsystem::Codegen, LogLevel::Error)],
+        };
+        assert!(!log_levels.enabled(LogLevel::Warn, LogSubsystem::Codegen));
+        assert!(log_levels.enabled(LogLevel::Warn, LogSubsystem::Linker));
+    }
+
+    #[test]
+    fn linkers_keep_their_own_config() {
+        let quiet = Linker::new(ConfigStruct {
+            warnings: WarningPolicy::Deny,
+            log_levels: LogLevels {
+                level: LogLevel::Warn,
+                filters: Vec::new(),
+            },
+            ..Default::default()
+        });
+        let verbose = Linker::new(ConfigStruct {
+            log_levels: LogLevels {
+                level: LogLevel::Debug,
+                filters: vec![(LogSubsystem::Latency, LogLevel::Trace)],
+            },
+            ..Default::default()
+        });
+
+        assert!(!log_enabled(&quiet.config, LogLevel::Info, LogSubsystem::Linker));
+        assert!(log_enabled(&verbose.config, LogLevel::Debug, LogSubsystem::Linker));
+        assert!(!log_enabled(&quiet.config, LogLevel::Trace, LogSubsystem::Latency));
+        assert!(log_enabled(&verbose.config, LogLevel::Trace, LogSubsystem::Latency));
+        assert!(!log_enabled(&verbose.config, LogLevel::Trace, LogSubsystem::Instantiation));
+
+        assert_eq!(quiet.config.warnings, WarningPolicy::Deny);
+        assert_eq!(verbose.config.warnings, WarningPolicy::Warn);
+    }
+}
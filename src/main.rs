@@ -1,5 +1,8 @@
 #![doc = include_str!("../README.md")]
 
+#[macro_use]
+mod logging;
+
 mod alloc;
 mod util;
 
@@ -15,130 +18,181 @@ mod typing;
 mod value;
 
 mod codegen_fallback;
+mod codegen_output;
 
 mod dev_aid;
 mod linker;
 
 mod compiler_top;
+mod project;
 
 use std::path::PathBuf;
 use std::{error::Error, fs};
-use std::fs::File;
-use std::io::Write;
-use std::ops::Deref;
 use std::rc::Rc;
 
-use prelude::*;
-
-use codegen_fallback::gen_verilog_code;
-use config::{config, parse_args};
+use codegen_output::{dependency_order, write_output};
+use config::{parse_args, FormatMode, MessageFormat, OutputLayout};
+use dev_aid::formatter::parse_and_format;
 use dev_aid::ariadne_interface::*;
+use dev_aid::json_diagnostics::print_all_errors_json;
+use dev_aid::sarif::make_sarif_log;
+use errors::DiagnosticCounts;
 use flattening::Module;
+use linker::Linker;
 use instantiation::InstantiatedModule;
 
-fn codegen_instance(inst: &InstantiatedModule, md: &Module, out_file: &mut File) {
-    let inst_name = &inst.name;
-    if inst.errors.did_error {
-        println!("Instantiating error: {inst_name}");
-        return; // Continue
+fn exit_on_output_error<T>(result: Result<T, Box<dyn Error + Sync + Send>>) -> T {
+    result.unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(1)
+    })
+}
+
+/// Returns whether all files were already formatted
+fn format_files(file_paths: &[PathBuf], mode: FormatMode) -> Result<bool, Box<dyn Error + Sync + Send>> {
+    let mut all_formatted = true;
+    for file_path in file_paths {
+        let text = fs::read_to_string(file_path)?;
+        let Some(formatted) = parse_and_format(&text) else {
+            eprintln!("Could not format {}, it contains syntax errors", file_path.display());
+            all_formatted = false;
+            continue;
+        };
+        if formatted == text {
+            continue;
+        }
+        all_formatted = false;
+        match mode {
+            FormatMode::InPlace => {
+                fs::write(file_path, formatted)?;
+                eprintln!("Formatted {}", file_path.display());
+            }
+            FormatMode::Check => eprintln!("{} is not formatted", file_path.display()),
+        }
     }
-    println!("Instantiating success: {inst_name}");
-    let code = gen_verilog_code(md, &inst, true);
-    write!(out_file, "// {inst_name}\n{code}").unwrap();
+    Ok(all_formatted)
 }
 
-fn make_output_file(name : &str) -> File {
-    let mut path = PathBuf::with_capacity(name.len()+"verilog_output/.sv".len());
-    path.push("verilog_output");
-    fs::create_dir_all(&path).unwrap();
-    path.push(name);
-    path.set_extension("sv");
-    let mut file = File::create(path).unwrap();
-
-    file.write_fmt(format_args!("// DO NOT EDIT THIS FILE\n// This file was generated with SUS Compiler {}", std::env!("CARGO_PKG_VERSION"))).unwrap();
-    
-    file
+/// Instantiate before printing errors, so errors in the top instances are reported too
+fn instantiate_tops(linker: &Linker) -> Vec<(&Module, Rc<InstantiatedModule>)> {
+    let mut top_instances = Vec::new();
+    for top in &linker.config.top_modules {
+        match linker.instantiate_top(top) {
+            Ok(found) => top_instances.push(found),
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+    top_instances
 }
 
-fn codegen_to_file(md: &Module) {
-    let mut out_file = make_output_file(md.link_info.name.deref());
-    md.instantiations.for_each_instance(|_template_args, inst| {
-        codegen_instance(inst.as_ref(), md, &mut out_file)
-    });
+fn print_diagnostics(linker: &Linker, file_sources: &mut FileSourcesManager) -> Result<DiagnosticCounts, Box<dyn Error + Sync + Send>> {
+    Ok(match linker.config.message_format {
+        MessageFormat::Human => print_all_errors(linker, &mut file_sources.file_sources),
+        MessageFormat::Json => print_all_errors_json(linker),
+        MessageFormat::Sarif => {
+            let (log, counts) = make_sarif_log(linker);
+            println!("{}", serde_json::to_string_pretty(&log)?);
+            counts
+        }
+    })
 }
 
-fn codegen_with_dependencies(linker: &Linker, md: &Module, file_name: &str) {
-    let mut out_file = make_output_file(file_name);
-    let mut top_level_instances: Vec<Rc<InstantiatedModule>> = Vec::new();
-    md.instantiations.for_each_instance(|_template_args, inst| {
-        top_level_instances.push(inst.clone());
-    });
-    let mut to_process_queue: Vec<(&InstantiatedModule, &Module)> = top_level_instances
-        .iter()
-        .map(|v| (v.as_ref(), md))
-        .collect();
-
-    let mut cur_idx = 0;
-
-    while cur_idx < to_process_queue.len() {
-        let (cur_instance, cur_md) = to_process_queue[cur_idx];
-
-        for (_, sub_mod) in &cur_instance.submodules {
-            let new_inst = sub_mod.instance.as_ref().unwrap().as_ref();
-
-            // Skip duplicates
-            // Yeah yeah I know O(n²) but this list shouldn't grow too big. Fix if needed
-            if to_process_queue
-                .iter()
-                .any(|existing| std::ptr::eq(existing.0, new_inst))
-            {
-                continue;
+/// Writes the code for `--codegen` and `--standalone`. Files whose content did not change are left untouched
+fn generate_code<'l>(linker: &'l Linker, top_instances: Vec<(&'l Module, Rc<InstantiatedModule>)>) -> Result<(), Box<dyn Error + Sync + Send>> {
+    let config = &linker.config;
+    if config.codegen {
+        let roots: Vec<(&Module, Rc<InstantiatedModule>)> = if config.top_modules.is_empty() {
+            let mut all_instances = Vec::new();
+            for (_id, md) in &linker.modules {
+                md.instantiations.for_each_instance(|_template_args, inst| all_instances.push((md, inst.clone())));
             }
+            all_instances
+        } else {
+            top_instances
+        };
+        let instances = dependency_order(linker, roots);
+        write_output(linker, &instances, config.output_layout, "design")?;
+    }
 
-            to_process_queue.push((new_inst, &linker.modules[sub_mod.module_uuid]));
-        }
-
-        codegen_instance(cur_instance, cur_md, &mut out_file);
+    if let Some(md_name) = &config.codegen_module_and_dependencies_one_file {
+        let Some(md) = linker
+            .modules
+            .iter()
+            .find(|(_, md)| &md.link_info.name == md_name) else {
+            return Err(format!("Unknown module {md_name}").into());
+        };
 
-        cur_idx += 1;
+        let mut roots = Vec::new();
+        md.1.instantiations.for_each_instance(|_template_args, inst| roots.push((md.1, inst.clone())));
+        let instances = dependency_order(linker, roots);
+        write_output(linker, &instances, OutputLayout::Single, &format!("{md_name}_standalone"))?;
     }
+    Ok(())
 }
 
 fn main() -> Result<(), Box<dyn Error + Sync + Send>> {
-    let file_paths = parse_args();
-
-    let config = config();
+    let (config, file_paths) = parse_args();
 
     if config.use_lsp {
         #[cfg(feature = "lsp")]
-        return dev_aid::lsp::lsp_main();
+        return dev_aid::lsp::lsp_main(config);
 
         #[cfg(not(feature = "lsp"))]
         panic!("LSP not enabled!")
     }
 
-    let (linker, mut paths_arena) = compile_all(file_paths);
-    print_all_errors(&linker, &mut paths_arena.file_sources);
+    if let Some(code) = config.explain {
+        println!("{} ({}): {}\n\n{}", code.code(), code.name(), code.summary(), code.explanation());
+        return Ok(());
+    }
 
-    if config.codegen {
-        for (_id, md) in &linker.modules {
-            codegen_to_file(md);
+    if let Some(mode) = config.format {
+        let all_formatted = format_files(&file_paths, mode)?;
+        if mode == FormatMode::Check && !all_formatted {
+            std::process::exit(1);
         }
+        return Ok(());
     }
 
-    if let Some(md_name) = &config.codegen_module_and_dependencies_one_file {
-        let Some(md) = linker
-            .modules
-            .iter()
-            .find(|(_, md)| &md.link_info.name == md_name) else {
-            
-            let mut err_lock = std::io::stderr().lock();
-            writeln!(err_lock, "Unknown module {md_name}").unwrap();
-            std::process::exit(1);
-        };
+    let (linker, mut paths_arena) = compile_all(config, file_paths.clone());
 
-        codegen_with_dependencies(&linker, md.1, &format!("{md_name}_standalone"));
+    if linker.config.watch {
+        dev_aid::watch::watch(linker, paths_arena, &file_paths, |linker, file_sources| {
+            let top_instances = instantiate_tops(linker);
+            match print_diagnostics(linker, file_sources) {
+                Ok(counts) if counts.denied_warnings > 0 => eprintln!("{} denied warnings found", counts.denied_warnings),
+                Ok(_) => {}
+                Err(err) => eprintln!("{err}"),
+            }
+            if let Err(err) = generate_code(linker, top_instances) {
+                eprintln!("{err}");
+            }
+        });
     }
 
+    let top_instances = instantiate_tops(&linker);
+
+    let counts = print_diagnostics(&linker, &mut paths_arena)?;
+
+    // Before the exit on denied warnings, because --fix often resolves them
+    if linker.config.apply_fixes {
+        for file_path in &file_paths {
+            let Some(file_id) = linker.find_file(&file_path.to_string_lossy()) else {
+                continue;
+            };
+            if let Some(fixed_text) = linker.apply_suggested_edits(file_id) {
+                fs::write(file_path, fixed_text)?;
+                eprintln!("Applied suggested fixes to {}", file_path.display());
+            }
+        }
+    }
+
+    if counts.denied_warnings > 0 {
+        eprintln!("{} denied warnings found", counts.denied_warnings);
+        std::process::exit(1);
+    }
+
+    exit_on_output_error(generate_code(&linker, top_instances));
+
     Ok(())
 }
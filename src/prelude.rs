@@ -4,7 +4,7 @@
 
 // public imports
 pub use crate::alloc::FlatAlloc;
-pub use crate::errors::ErrorCollector;
+pub use crate::errors::{DiagnosticCode, ErrorCollector};
 pub use crate::file_position::{BracketSpan, Span, SpanFile};
 pub use crate::linker::Linker;
 
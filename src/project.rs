@@ -0,0 +1,439 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::compiler_top::sus_files_in_directory;
+use crate::config::{ConfigStruct, OutputLayout, WarningPolicy};
+
+pub const MANIFEST_FILE_NAME: &str = "sus.toml";
+
+/// The contents of a `sus.toml` project manifest
+///
+/// ```toml
+/// warnings = "deny"
+///
+/// [project]
+/// name = "my_project"
+/// sources = ["src"]
+///
+/// [dependencies]
+/// my_lib = { path = "../my_lib" }
+///
+/// [[top]]
+/// module = "FIFO"
+/// template_args = { DEPTH = 32, T = "int" }
+///
+/// [output]
+/// directory = "verilog_output"
+/// layout = "per-module"
+/// backend = "systemverilog"
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub project: ProjectSection,
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, Dependency>,
+    /// The modules to generate code for, together with their dependencies. If empty, all modules are generated
+    #[serde(default)]
+    pub top: Vec<TopModule>,
+    #[serde(default)]
+    pub output: OutputSection,
+    #[serde(default)]
+    pub warnings: WarningPolicy,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectSection {
+    pub name: String,
+    /// Directories (searched recursively) or single files, relative to the manifest
+    #[serde(default = "default_sources")]
+    pub sources: Vec<PathBuf>,
+}
+
+fn default_sources() -> Vec<PathBuf> {
+    vec![PathBuf::from(".")]
+}
+
+/// A library on the local file system. If it has its own `sus.toml`, its sources and dependencies are used,
+/// otherwise all .sus files in the directory are
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Dependency {
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TopModule {
+    pub module: String,
+    #[serde(default)]
+    pub template_args: BTreeMap<String, TopTemplateArg>,
+    /// Unnamed values, in the order of the module's generative template arguments. Only given through `--top`
+    #[serde(skip)]
+    pub positional_values: Vec<TopTemplateArg>,
+    /// Unnamed types, in the order of the module's type template arguments. Only given through `--top`
+    #[serde(skip)]
+    pub positional_types: Vec<String>,
+}
+
+/// Splits on the separator, except inside brackets
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (idx, c) in text.char_indices() {
+        match c {
+            '[' | '(' | '<' => depth += 1,
+            ']' | ')' | '>' => depth = depth.saturating_sub(1),
+            c if c == separator && depth == 0 => {
+                result.push(&text[start..idx]);
+                start = idx + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    result.push(&text[start..]);
+    result
+}
+
+/// Splits `NAME = arg` into its name and argument
+fn split_named_arg(arg: &str) -> (Option<&str>, &str) {
+    match split_top_level(arg, '=').as_slice() {
+        [name, value] => (Some(name.trim()), value.trim()),
+        _ => (None, arg.trim()),
+    }
+}
+
+fn parse_template_value(text: &str) -> Result<TopTemplateArg, String> {
+    let text = text.trim();
+    if let Some(elements) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+        if elements.trim().is_empty() {
+            return Ok(TopTemplateArg::Array(Vec::new()));
+        }
+        return split_top_level(elements, ',')
+            .into_iter()
+            .map(parse_template_value)
+            .collect::<Result<Vec<_>, _>>()
+            .map(TopTemplateArg::Array);
+    }
+    match text {
+        "true" => Ok(TopTemplateArg::Bool(true)),
+        "false" => Ok(TopTemplateArg::Bool(false)),
+        _ => text
+            .parse()
+            .map(TopTemplateArg::Integer)
+            .map_err(|_| format!("Invalid template argument value '{text}'. Expected an integer, a bool or an array")),
+    }
+}
+
+impl TopModule {
+    /// Parses the template instantiation syntax of SUS, such as `FIFO::<DEPTH = 64, READY_SLACK = 3; int>`.
+    /// Values come before the `;` and types after it. Both can be given by name or by position
+    pub fn parse(text: &str) -> Result<TopModule, String> {
+        let text = text.trim();
+        let mut top = TopModule {
+            module: text.to_owned(),
+            template_args: BTreeMap::new(),
+            positional_values: Vec::new(),
+            positional_types: Vec::new(),
+        };
+        let Some((module, args)) = text.split_once("::") else {
+            return Ok(top);
+        };
+        let Some(args) = args.trim().strip_prefix('<').and_then(|a| a.strip_suffix('>')) else {
+            return Err(format!("Invalid top module '{text}'. Expected Module::<values; types>"));
+        };
+        top.module = module.trim().to_owned();
+
+        let (values, types) = args.split_once(';').unwrap_or((args, ""));
+        for (list, is_type) in [(values, false), (types, true)] {
+            if list.trim().is_empty() {
+                continue;
+            }
+            for arg in split_top_level(list, ',') {
+                let (name, arg) = split_named_arg(arg);
+                if arg.is_empty() {
+                    return Err(format!("Empty template argument in '{text}'"));
+                }
+                let arg = if is_type {
+                    TopTemplateArg::Type(arg.to_owned())
+                } else {
+                    parse_template_value(arg)?
+                };
+                match (name, arg) {
+                    (Some(name), arg) => {
+                        if top.template_args.insert(name.to_owned(), arg).is_some() {
+                            return Err(format!("Template argument '{name}' is given twice in '{text}'"));
+                        }
+                    }
+                    (None, TopTemplateArg::Type(typ)) => top.positional_types.push(typ),
+                    (None, value) => top.positional_values.push(value),
+                }
+            }
+        }
+        Ok(top)
+    }
+}
+
+/// Strings name types, such as `"int"` or `"bool[4]"`. Everything else is a generative value
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum TopTemplateArg {
+    Bool(bool),
+    Integer(i64),
+    Array(Vec<TopTemplateArg>),
+    Type(String),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputSection {
+    #[serde(default = "default_output_directory")]
+    pub directory: PathBuf,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub layout: OutputLayout,
+}
+
+impl Default for OutputSection {
+    fn default() -> Self {
+        Self {
+            directory: default_output_directory(),
+            backend: Backend::default(),
+            layout: OutputLayout::default(),
+        }
+    }
+}
+
+fn default_output_directory() -> PathBuf {
+    PathBuf::from("verilog_output")
+}
+
+/// The language to generate. Manifests asking for any other backend are rejected
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    SystemVerilog,
+}
+
+/// A loaded manifest, with all paths in it resolved relative to the directory it was found in
+#[derive(Debug)]
+pub struct Project {
+    pub root: PathBuf,
+    pub manifest: Manifest,
+}
+
+impl Project {
+    /// Returns Ok(None) if there's no manifest in the directory
+    pub fn find_in(directory: &Path) -> Result<Option<Project>, String> {
+        let manifest_path = directory.join(MANIFEST_FILE_NAME);
+        if !manifest_path.is_file() {
+            return Ok(None);
+        }
+        Self::load(&manifest_path).map(Some)
+    }
+
+    pub fn load(manifest_path: &Path) -> Result<Project, String> {
+        let text = std::fs::read_to_string(manifest_path)
+            .map_err(|reason| format!("Could not read {}: {reason}", manifest_path.display()))?;
+        let manifest: Manifest = toml::from_str(&text)
+            .map_err(|reason| format!("Invalid project manifest {}: {reason}", manifest_path.display()))?;
+        let root = manifest_path
+            .parent()
+            .map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+        Ok(Project { root, manifest })
+    }
+
+    /// All .sus files of this project and of its dependencies, recursively. See [sus_files_in_directory]
+    pub fn source_files(&self, config: &ConfigStruct) -> Result<Vec<PathBuf>, String> {
+        let mut result = Vec::new();
+        let mut visited_projects = HashSet::new();
+        self.gather_source_files(config, &mut result, &mut visited_projects)?;
+        // Dependencies may be nested in the source directories
+        let mut seen = HashSet::new();
+        result.retain(|path| seen.insert(path.canonicalize().unwrap_or_else(|_| path.clone())));
+        Ok(result)
+    }
+
+    fn gather_source_files(&self, config: &ConfigStruct, result: &mut Vec<PathBuf>, visited_projects: &mut HashSet<PathBuf>) -> Result<(), String> {
+        let canonical_root = self.root.canonicalize().unwrap_or_else(|_| self.root.clone());
+        if !visited_projects.insert(canonical_root) {
+            return Ok(()); // Dependency cycle or diamond, already included
+        }
+
+        for source in &self.manifest.project.sources {
+            let source_path = self.root.join(source);
+            if source_path.is_file() {
+                result.push(source_path);
+            } else if source_path.is_dir() {
+                result.extend(directory_sus_files(config, &source_path)?);
+            } else {
+                return Err(format!("Source path {} of project '{}' does not exist", source_path.display(), self.manifest.project.name));
+            }
+        }
+
+        for (dep_name, dep) in &self.manifest.dependencies {
+            let dep_path = self.root.join(&dep.path);
+            if !dep_path.is_dir() {
+                return Err(format!("Dependency '{dep_name}' at {} is not a directory", dep_path.display()));
+            }
+            match Project::find_in(&dep_path)? {
+                Some(dep_project) => dep_project.gather_source_files(config, result, visited_projects)?,
+                None => result.extend(directory_sus_files(config, &dep_path)?),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn directory_sus_files(config: &ConfigStruct, directory: &Path) -> Result<Vec<PathBuf>, String> {
+    sus_files_in_directory(config, directory)
+        .map_err(|reason| format!("Could not read directory {}: {reason}", directory.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_manifest(text: &str) -> Result<Manifest, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    #[test]
+    fn full_manifest() {
+        let manifest = parse_manifest(
+            r#"
+            warnings = "deny"
+
+            [project]
+            name = "my_project"
+            sources = ["src", "extra.sus"]
+
+            [dependencies]
+            my_lib = { path = "../my_lib" }
+
+            [[top]]
+            module = "FIFO"
+            template_args = { DEPTH = 32, T = "int", INIT = [true, false] }
+
+            [output]
+            directory = "out"
+            layout = "per-instance"
+            backend = "systemverilog"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.warnings, WarningPolicy::Deny);
+        assert_eq!(manifest.project.name, "my_project");
+        assert_eq!(manifest.project.sources, vec![PathBuf::from("src"), PathBuf::from("extra.sus")]);
+        assert_eq!(manifest.dependencies["my_lib"].path, PathBuf::from("../my_lib"));
+        assert_eq!(manifest.output.directory, PathBuf::from("out"));
+        assert_eq!(manifest.output.layout, OutputLayout::PerInstance);
+        assert_eq!(manifest.output.backend, Backend::SystemVerilog);
+
+        let [top] = manifest.top.as_slice() else { panic!("Expected one top module") };
+        assert_eq!(top.module, "FIFO");
+        assert!(matches!(top.template_args["DEPTH"], TopTemplateArg::Integer(32)));
+        assert!(matches!(&top.template_args["T"], TopTemplateArg::Type(typ) if typ == "int"));
+        assert!(matches!(
+            top.template_args["INIT"].clone(),
+            TopTemplateArg::Array(elements) if matches!(elements.as_slice(), [TopTemplateArg::Bool(true), TopTemplateArg::Bool(false)])
+        ));
+    }
+
+    #[test]
+    fn manifest_defaults() {
+        let manifest = parse_manifest("[project]\nname = \"minimal\"\n").unwrap();
+
+        assert_eq!(manifest.project.sources, vec![PathBuf::from(".")]);
+        assert!(manifest.dependencies.is_empty());
+        assert!(manifest.top.is_empty());
+        assert_eq!(manifest.warnings, WarningPolicy::Warn);
+        assert_eq!(manifest.output.directory, PathBuf::from("verilog_output"));
+        assert_eq!(manifest.output.layout, OutputLayout::PerModule);
+    }
+
+    #[test]
+    fn invalid_manifests() {
+        // Missing [project]
+        assert!(parse_manifest("warnings = \"deny\"\n").is_err());
+        // Typos are reported instead of ignored
+        assert!(parse_manifest("[project]\nname = \"a\"\nsource = [\"src\"]\n").is_err());
+        assert!(parse_manifest("[project]\nname = \"a\"\n[output]\nlayout = \"per_module\"\n").is_err());
+        // Only SystemVerilog can be generated
+        assert!(parse_manifest("[project]\nname = \"a\"\n[output]\nbackend = \"vhdl\"\n").is_err());
+    }
+
+    #[test]
+    fn top_module_without_arguments() {
+        let top = TopModule::parse(" FIFO ").unwrap();
+        assert_eq!(top.module, "FIFO");
+        assert!(top.template_args.is_empty());
+        assert!(top.positional_values.is_empty());
+        assert!(top.positional_types.is_empty());
+    }
+
+    #[test]
+    fn top_module_with_arguments() {
+        let top = TopModule::parse("FIFO::<DEPTH = 64, 3, [1, [2, 3]]; T = bool[4], int>").unwrap();
+        assert_eq!(top.module, "FIFO");
+        assert!(matches!(top.template_args["DEPTH"], TopTemplateArg::Integer(64)));
+        assert!(matches!(&top.template_args["T"], TopTemplateArg::Type(typ) if typ == "bool[4]"));
+        assert_eq!(top.positional_types, vec!["int".to_owned()]);
+
+        let [TopTemplateArg::Integer(3), TopTemplateArg::Array(array)] = top.positional_values.as_slice() else {
+            panic!("Unexpected positional values {:?}", top.positional_values)
+        };
+        assert!(matches!(
+            array.as_slice(),
+            [TopTemplateArg::Integer(1), TopTemplateArg::Array(inner)] if matches!(inner.as_slice(), [TopTemplateArg::Integer(2), TopTemplateArg::Integer(3)])
+        ));
+
+        let only_types = TopModule::parse("Reg::<; int>").unwrap();
+        assert!(only_types.positional_values.is_empty());
+        assert_eq!(only_types.positional_types, vec!["int".to_owned()]);
+    }
+
+    #[test]
+    fn invalid_top_modules() {
+        assert!(TopModule::parse("FIFO::DEPTH").is_err());
+        assert!(TopModule::parse("FIFO::<DEPTH = 1, DEPTH = 2>").is_err());
+        assert!(TopModule::parse("FIFO::<1,,2>").is_err());
+        assert!(TopModule::parse("FIFO::<abc>").is_err());
+    }
+
+    #[test]
+    fn source_files_of_project_and_dependencies() {
+        let root = std::env::temp_dir().join(format!("sus_project_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let app = root.join("app");
+        let lib = root.join("lib");
+        std::fs::create_dir_all(app.join("src/nested")).unwrap();
+        std::fs::create_dir_all(&lib).unwrap();
+        std::fs::write(app.join(MANIFEST_FILE_NAME), "[project]\nname = \"app\"\nsources = [\"src\", \"src/b.sus\"]\n[dependencies]\nlib = { path = \"../lib\" }\n").unwrap();
+        std::fs::create_dir_all(app.join("src/.hidden")).unwrap();
+        for file in ["src/b.sus", "src/a.sus", "src/nested/c.sus", "src/notes.txt", "src/.hidden/d.sus"] {
+            std::fs::write(app.join(file), "").unwrap();
+        }
+        std::fs::write(lib.join("lib.sus"), "").unwrap();
+
+        let project = Project::find_in(&app).unwrap().unwrap();
+        let files = project.source_files(&ConfigStruct::default());
+        std::fs::remove_dir_all(&root).unwrap();
+
+        // src/b.sus is listed twice, but only included once. Hidden directories are skipped
+        assert_eq!(
+            files.unwrap(),
+            vec![app.join("src/a.sus"), app.join("src/b.sus"), app.join("src/nested/c.sus"), app.join("../lib").join("lib.sus")]
+        );
+        assert!(Project::find_in(&root).unwrap().is_none());
+    }
+}
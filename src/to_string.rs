@@ -230,19 +230,19 @@ impl Module {
     }
 
     pub fn print_flattened_module(&self, file_data: &FileData) {
-        println!("[[{}]]:", self.link_info.name);
-        println!("Interface:");
+        eprintln!("[[{}]]:", self.link_info.name);
+        eprintln!("Interface:");
         for (port_id, port) in &self.ports {
-            println!(
+            eprintln!(
                 "    {} -> {:?}",
                 self.make_port_info_string(port_id, &file_data.file_text),
                 port
             );
         }
-        println!("Instructions:");
+        eprintln!("Instructions:");
         let mut spans_print = Vec::new();
         for (id, inst) in &self.instructions {
-            println!("    {id:?}: {inst:?}");
+            eprintln!("    {id:?}: {inst:?}");
             let span = self.get_instruction_span(id);
             spans_print.push((format!("{id:?}"), span.into_range()));
         }
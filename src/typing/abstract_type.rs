@@ -127,6 +127,8 @@ pub struct TypeUnifier<'linker, 'errs> {
     domain_substitutor: RefCell<FlatAlloc<DomainTypeSubstitution, DomainIDMarker>>,
     errors: &'errs ErrorCollector<'linker>,
     pub final_domains: FlatAlloc<BestName, DomainIDMarker>,
+    /// Declarations created by `cross`, with the domain they were crossed from and the domain they were crossed into
+    crossings: RefCell<Vec<(FlatID, DomainID, DomainID)>>,
 }
 
 impl<'linker, 'errs> TypeUnifier<'linker, 'errs> {
@@ -145,6 +147,7 @@ impl<'linker, 'errs> TypeUnifier<'linker, 'errs> {
             errors,
             domain_substitutor: RefCell::new(domains),
             final_domains,
+            crossings: RefCell::new(Vec::new()),
         }
     }
 
@@ -174,7 +177,7 @@ impl<'linker, 'errs> TypeUnifier<'linker, 'errs> {
         if !self.type_compare(expected, found) {
             let expected_name = expected.to_string(&self.linker_types, &self.template_type_names);
             let found_name = found.to_string(&self.linker_types, &self.template_type_names);
-            let err_ref = self.errors.error(span, format!("Typing Error: {context} expects a {expected_name} but was given a {found_name}"));
+            let err_ref = self.errors.error(DiagnosticCode::TypeError, span, format!("Typing Error: {context} expects a {expected_name} but was given a {found_name}"));
             if let Some(declared_here) = declared_here {
                 err_ref.info(declared_here, "Declared here");
             }
@@ -269,7 +272,7 @@ impl<'linker, 'errs> TypeUnifier<'linker, 'errs> {
     ) -> AbstractType {
         let AbstractType::Array(arr_element_type) = arr_type else {
             let arr_type_name = arr_type.to_string(&self.linker_types, &self.template_type_names);
-            self.errors.error(arr_span, format!("Typing Error: Attempting to index into this, but it is not of array type, instead found a {arr_type_name}"));
+            self.errors.error(DiagnosticCode::TypeError, arr_span, format!("Typing Error: Attempting to index into this, but it is not of array type, instead found a {arr_type_name}"));
             return AbstractType::Error;
         };
         arr_element_type.deref().clone()
@@ -384,6 +387,33 @@ impl<'linker, 'errs> TypeUnifier<'linker, 'errs> {
         }
     }
 
+    /// The domain of a wire crossed by a `cross` statement. Wires crossed by the same statement are combined into the domain of the first one, `group_domain`.
+    ///
+    /// Unlike [Self::combine_domains], the crossed wire is not unified with the domain it came from. See [Self::redundant_crossings]
+    pub fn cross_domain(&self, crossed_decl: FlatID, from: &DomainType, group_domain: Option<&DomainType>) -> DomainType {
+        let new_domain = self.new_unknown_domain(false);
+        let to = match group_domain {
+            Some(group_domain) => self.combine_domains::<false, _>(group_domain, &new_domain, |_, _| {
+                unreachable!("A fresh domain can always be merged")
+            }),
+            None => new_domain,
+        };
+        if let (DomainType::Physical(from), DomainType::Physical(to)) = (from, &to) {
+            self.crossings.borrow_mut().push((crossed_decl, *from, *to));
+        }
+        to
+    }
+
+    /// The `cross` declarations that were unified with the domain they were crossed from, and so don't actually cross anything
+    pub fn redundant_crossings(&self) -> Vec<FlatID> {
+        self.crossings
+            .borrow()
+            .iter()
+            .filter(|(_, from, to)| self.get_root_domain(*from) == self.get_root_domain(*to))
+            .map(|(crossed_decl, _, _)| *crossed_decl)
+            .collect()
+    }
+
     // ===== Both =====
 
     pub fn typecheck_and_generative<const MUST_BE_GENERATIVE: bool>(
@@ -399,7 +429,7 @@ impl<'linker, 'errs> TypeUnifier<'linker, 'errs> {
         if MUST_BE_GENERATIVE && found.domain != DomainType::Generative {
             let err_ref = self
                 .errors
-                .error(span, format!("A generative value is required in {context}"));
+                .error(DiagnosticCode::GenerativeRequired, span, format!("A generative value is required in {context}"));
             if let Some(span_file) = declared_here {
                 err_ref.info(span_file, "Declared here");
             }
@@ -422,7 +452,7 @@ impl<'linker, 'errs> TypeUnifier<'linker, 'errs> {
             } else { // Expected is generative
                 format!("Cannot write to a generative wire from a non-generative wire in domain '{found_domain}'")
             };
-            let err_ref = self.errors.error(span, err_text);
+            let err_ref = self.errors.error(DiagnosticCode::DomainMismatch, span, err_text);
             if let Some(declared_here) = declared_here {
                 err_ref.info(declared_here, "Declared here");
             }
@@ -452,12 +482,35 @@ impl<'linker, 'errs> TypeUnifier<'linker, 'errs> {
             typ : self.typecheck_binary_operator_abstr(op, &left_typ.typ, &right_typ.typ, left_span, right_span),
             domain : self.combine_domains::<false, _>(&left_typ.domain, &right_typ.domain, |left_name, right_name| {
                 let left_name = left_name.unwrap();
-                self.errors.error(right_span, format!("Attempting to combine wires of different domains. The domain for this wire is '{right_name}' and the other is '{left_name}'"))
+                self.errors.error(DiagnosticCode::DomainMismatch, right_span, format!("Attempting to combine wires of different domains. The domain for this wire is '{right_name}' and the other is '{left_name}'"))
                     .info_same_file(left_span, format!("Other wire in domain '{left_name}'"));
             })
         }
     }
 
+    /// All elements must have the same type as the first, and are merged into one domain
+    pub fn typecheck_array_literal<'t, I: Iterator<Item = (&'t FullType, Span)>>(
+        &self,
+        mut elements: I,
+    ) -> FullType {
+        let (first_typ, first_span) = elements
+            .next()
+            .expect("Empty array literals are rejected in flattening");
+        let mut domain = first_typ.domain;
+        for (elem_typ, elem_span) in elements {
+            self.typecheck_abstr(&elem_typ.typ, elem_span, &first_typ.typ, "array literal element", None);
+            domain = self.combine_domains::<false, _>(&domain, &elem_typ.domain, |arr_domain, elem_domain| {
+                let arr_domain = arr_domain.unwrap();
+                self.errors.error(DiagnosticCode::DomainMismatch, elem_span, format!("Attempting to combine wires of different domains in an array literal. The domain for this element is '{elem_domain}' but the previous elements are '{arr_domain}'"))
+                    .info_same_file(first_span, "First element");
+            });
+        }
+        FullType {
+            typ: AbstractType::Array(Box::new(first_typ.typ.clone())),
+            domain,
+        }
+    }
+
     pub fn typecheck_array_access(
         &self,
         arr_type: &FullType,
@@ -470,7 +523,7 @@ impl<'linker, 'errs> TypeUnifier<'linker, 'errs> {
             typ : self.typecheck_is_array_abstr(&arr_type.typ, arr_span),
             domain : self.combine_domains::<false, _>(&arr_type.domain, &idx_type.domain, |arr_domain, idx_domain| {
                 let arr_domain = arr_domain.unwrap();
-                self.errors.error(idx_span, format!("Attempting to index into an array of a different domain. The domain for this index is '{idx_domain}' but the array is '{arr_domain}'"));
+                self.errors.error(DiagnosticCode::DomainMismatch, idx_span, format!("Attempting to index into an array of a different domain. The domain for this index is '{idx_domain}' but the array is '{arr_domain}'"));
             })
         }
     }
@@ -506,6 +559,7 @@ impl<'linker, 'errs> TypeUnifier<'linker, 'errs> {
         }
         if typ.typ.contains_error_or_unknown::<true, true>() {
             self.errors.error(
+                DiagnosticCode::TypeError,
                 span,
                 format!(
                     "Unresolved Type: {}",
@@ -105,6 +105,19 @@ impl ConcreteType {
         };
         v
     }
+    pub fn new_array(elem_typ: ConcreteType, size: usize) -> ConcreteType {
+        ConcreteType::Array(Box::new((
+            elem_typ,
+            ConcreteType::Value(Value::Integer(size.into())),
+        )))
+    }
+    pub fn unwrap_array_size(&self) -> usize {
+        let ConcreteType::Array(arr_box) = self else {
+            unreachable!("Must be an array!")
+        };
+        let (_sub, sz) = arr_box.deref();
+        sz.unwrap_value().unwrap_usize()
+    }
     pub fn down_array(&self) -> &ConcreteType {
         let ConcreteType::Array(arr_box) = self else {
             unreachable!("Must be an array!")
@@ -139,6 +152,7 @@ impl ConcreteType {
     ) {
         if !self.type_compare(source_type) {
             errors.error(
+                DiagnosticCode::TypeError,
                 span,
                 format!(
                     "Concrete Type Error! Expected {} but found {}",
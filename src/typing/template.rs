@@ -3,7 +3,7 @@ use crate::prelude::*;
 use super::concrete_type::ConcreteType;
 use crate::{flattening::WrittenType, linker::LinkInfo, value::TypedValue};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GlobalReference<ID> {
     pub span: Span,
     pub id: ID,
@@ -51,14 +51,14 @@ impl TemplateInputKind {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TemplateArg {
     pub name_specification: Option<Span>,
     pub whole_span: Span,
     pub kind: TemplateArgKind,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum TemplateArgKind {
     Type(WrittenType),
     Value(FlatID),
@@ -115,7 +115,7 @@ pub fn check_all_template_args_valid(
             write!(uncovered_ports_list, "'{}', ", v.name).unwrap();
         }
         uncovered_ports_list.truncate(uncovered_ports_list.len() - 2); // Cut off last comma
-        let err_ref = errors.error(span, format!("Could not instantiate {} because the template arguments {uncovered_ports_list} were missing and no default was provided", target_link_info.get_full_name()));
+        let err_ref = errors.error(DiagnosticCode::TemplateArguments, span, format!("Could not instantiate {} because the template arguments {uncovered_ports_list} were missing and no default was provided", target_link_info.get_full_name()));
         for v in &not_found_list {
             err_ref.info(
                 (v.name_span, target_link_info.file),
@@ -24,14 +24,13 @@ impl Value {
             typ: match self {
                 Value::Bool(_) => BOOL_TYPE,
                 Value::Integer(_) => INT_TYPE,
-                Value::Array(_b) => {
-                    unreachable!("Can't express arrays as constants (yet?)");
-                    /*let content_typ = if let Some(b_first) = b.first() {
-                        b_first.get_type()
+                Value::Array(b) => {
+                    let content_typ = if let Some(b_first) = b.first() {
+                        b_first.get_type_of_constant().typ
                     } else {
-                        Type::Invalid
-                    }
-                    Type::Array(Box::new((content_typ, b.len())))*/
+                        AbstractType::Error
+                    };
+                    AbstractType::Array(Box::new(content_typ))
                 }
                 Value::Unset => AbstractType::Error,
                 Value::Error => AbstractType::Error,
@@ -43,14 +42,11 @@ impl Value {
         match self {
             Value::Bool(_) => BOOL_CONCRETE_TYPE,
             Value::Integer(_) => INT_CONCRETE_TYPE,
-            Value::Array(_b) => {
-                unreachable!("Can't express arrays as constants (yet?)");
-                /*let content_typ = if let Some(b_first) = b.first() {
-                    b_first.get_type()
-                } else {
-                    Type::Invalid
-                }
-                Type::Array(Box::new((content_typ, b.len())))*/
+            Value::Array(b) => {
+                let Some(b_first) = b.first() else {
+                    unreachable!("Can't get the type of an empty array constant")
+                };
+                ConcreteType::new_array(b_first.get_concrete_type_of_constant(), b.len())
             }
             Value::Unset | Value::Error => unreachable!(),
         }
@@ -101,6 +97,14 @@ impl Value {
         };
         *b
     }
+
+    #[track_caller]
+    pub fn unwrap_array(&self) -> &[Value] {
+        let Self::Array(arr) = self else {
+            panic!("{:?} is not an array!", self)
+        };
+        arr
+    }
 }
 
 pub fn compute_unary_op(op: UnaryOperator, v: &TypedValue) -> TypedValue {
@@ -109,14 +113,8 @@ pub fn compute_unary_op(op: UnaryOperator, v: &TypedValue) -> TypedValue {
         //return TypedValue{typ : , value : Value::Error}
     }
     match op {
-        UnaryOperator::Or => {
-            todo!("Array Values")
-        }
-        UnaryOperator::And => {
-            todo!("Array Values")
-        }
-        UnaryOperator::Xor => {
-            todo!("Array Values")
+        UnaryOperator::Or | UnaryOperator::And | UnaryOperator::Xor | UnaryOperator::Sum | UnaryOperator::Product => {
+            compute_reduction(op, v.value.unwrap_array())
         }
         UnaryOperator::Not => {
             assert_eq!(v.typ, BOOL_CONCRETE_TYPE);
@@ -125,12 +123,6 @@ pub fn compute_unary_op(op: UnaryOperator, v: &TypedValue) -> TypedValue {
             };
             TypedValue::make_bool(!*b)
         }
-        UnaryOperator::Sum => {
-            todo!("Array Values")
-        }
-        UnaryOperator::Product => {
-            todo!("Array Values")
-        }
         UnaryOperator::Negate => {
             assert_eq!(v.typ, INT_CONCRETE_TYPE);
             let Value::Integer(v) = &v.value else {
@@ -141,6 +133,30 @@ pub fn compute_unary_op(op: UnaryOperator, v: &TypedValue) -> TypedValue {
     }
 }
 
+/// Reduces an array of bools or integers to a single value.
+///
+/// If any element is [Value::Error] or [Value::Unset], the result is that same value rather than a panic
+fn compute_reduction(op: UnaryOperator, arr: &[Value]) -> TypedValue {
+    let typ = match op {
+        UnaryOperator::Or | UnaryOperator::And | UnaryOperator::Xor => BOOL_CONCRETE_TYPE,
+        UnaryOperator::Sum | UnaryOperator::Product => INT_CONCRETE_TYPE,
+        UnaryOperator::Not | UnaryOperator::Negate => unreachable!("{op:?} is not a reduction"),
+    };
+    for poison in [Value::Error, Value::Unset] {
+        if arr.contains(&poison) {
+            return TypedValue { typ, value: poison };
+        }
+    }
+    match op {
+        UnaryOperator::Or => TypedValue::make_bool(arr.iter().any(|b| b.unwrap_bool())),
+        UnaryOperator::And => TypedValue::make_bool(arr.iter().all(|b| b.unwrap_bool())),
+        UnaryOperator::Xor => TypedValue::make_bool(arr.iter().fold(false, |acc, b| acc ^ b.unwrap_bool())),
+        UnaryOperator::Sum => TypedValue::make_integer(arr.iter().map(|i| i.unwrap_integer()).sum()),
+        UnaryOperator::Product => TypedValue::make_integer(arr.iter().map(|i| i.unwrap_integer()).product()),
+        UnaryOperator::Not | UnaryOperator::Negate => unreachable!(),
+    }
+}
+
 pub fn compute_binary_op(left: &TypedValue, op: BinaryOperator, right: &TypedValue) -> TypedValue {
     if left.value == Value::Error || right.value == Value::Error {
         unreachable!("binary op on Value::Error!")
@@ -199,6 +215,13 @@ impl TypedValue {
             value: Value::Integer(i),
         }
     }
+    /// All values must be of type `elem_typ`
+    pub fn make_array(elem_typ: ConcreteType, values: Vec<Value>) -> Self {
+        Self {
+            typ: ConcreteType::new_array(elem_typ, values.len()),
+            value: Value::Array(values.into_boxed_slice()),
+        }
+    }
     /// panics if the value can't be typed.
     pub fn from_value(value: Value) -> Self {
         Self {
@@ -243,3 +266,86 @@ impl ConcreteType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int_array(values: &[i64]) -> Value {
+        Value::Array(values.iter().map(|v| Value::Integer(BigInt::from(*v))).collect())
+    }
+
+    #[test]
+    fn array_constants_have_a_type() {
+        let table = int_array(&[3, 1, 4]);
+        assert_eq!(table.get_concrete_type_of_constant(), ConcreteType::new_array(INT_CONCRETE_TYPE, 3));
+        let AbstractType::Array(content) = table.get_type_of_constant().typ else {
+            panic!("Should be an array")
+        };
+        assert!(matches!(*content, AbstractType::Named(_)));
+
+        let nested = Value::Array(vec![table.clone(), table].into_boxed_slice());
+        assert_eq!(
+            nested.get_concrete_type_of_constant(),
+            ConcreteType::new_array(ConcreteType::new_array(INT_CONCRETE_TYPE, 3), 2)
+        );
+    }
+
+    #[test]
+    fn make_array() {
+        let repeated = TypedValue::make_array(BOOL_CONCRETE_TYPE, vec![Value::Bool(true); 4]);
+        assert_eq!(repeated.typ, ConcreteType::new_array(BOOL_CONCRETE_TYPE, 4));
+        assert_eq!(repeated.value, Value::Array(vec![Value::Bool(true); 4].into_boxed_slice()));
+    }
+
+    fn bool_array(values: &[bool]) -> TypedValue {
+        TypedValue::make_array(BOOL_CONCRETE_TYPE, values.iter().map(|b| Value::Bool(*b)).collect())
+    }
+
+    fn reduce(op: UnaryOperator, value: Value) -> TypedValue {
+        compute_unary_op(op, &TypedValue::from_value(value))
+    }
+
+    #[test]
+    fn bool_reductions() {
+        let mixed = bool_array(&[true, false, true]);
+        assert_eq!(compute_unary_op(UnaryOperator::Or, &mixed), TypedValue::make_bool(true));
+        assert_eq!(compute_unary_op(UnaryOperator::And, &mixed), TypedValue::make_bool(false));
+        assert_eq!(compute_unary_op(UnaryOperator::Xor, &mixed), TypedValue::make_bool(false));
+
+        let all_set = bool_array(&[true, true, true]);
+        assert_eq!(compute_unary_op(UnaryOperator::And, &all_set), TypedValue::make_bool(true));
+        assert_eq!(compute_unary_op(UnaryOperator::Xor, &all_set), TypedValue::make_bool(true));
+    }
+
+    #[test]
+    fn integer_reductions() {
+        assert_eq!(reduce(UnaryOperator::Sum, int_array(&[3, 1, 4])), TypedValue::make_integer(8.into()));
+        assert_eq!(reduce(UnaryOperator::Product, int_array(&[3, -1, 4])), TypedValue::make_integer((-12).into()));
+    }
+
+    #[test]
+    fn empty_reductions_give_the_identity() {
+        let no_bools = bool_array(&[]);
+        assert_eq!(compute_unary_op(UnaryOperator::Or, &no_bools), TypedValue::make_bool(false));
+        assert_eq!(compute_unary_op(UnaryOperator::And, &no_bools), TypedValue::make_bool(true));
+        assert_eq!(compute_unary_op(UnaryOperator::Xor, &no_bools), TypedValue::make_bool(false));
+
+        let no_ints = TypedValue::make_array(INT_CONCRETE_TYPE, Vec::new());
+        assert_eq!(compute_unary_op(UnaryOperator::Sum, &no_ints), TypedValue::make_integer(0.into()));
+        assert_eq!(compute_unary_op(UnaryOperator::Product, &no_ints), TypedValue::make_integer(1.into()));
+    }
+
+    #[test]
+    fn reductions_propagate_unset_and_error() {
+        let partially_set = TypedValue::make_array(BOOL_CONCRETE_TYPE, vec![Value::Bool(true), Value::Unset]);
+        let result = compute_unary_op(UnaryOperator::Or, &partially_set);
+        assert_eq!(result.value, Value::Unset);
+        assert_eq!(result.typ, BOOL_CONCRETE_TYPE);
+
+        let broken = TypedValue::make_array(INT_CONCRETE_TYPE, vec![Value::Unset, Value::Error]);
+        let result = compute_unary_op(UnaryOperator::Sum, &broken);
+        assert_eq!(result.value, Value::Error);
+        assert_eq!(result.typ, INT_CONCRETE_TYPE);
+    }
+}